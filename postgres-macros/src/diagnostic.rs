@@ -0,0 +1,126 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use syn::LitStr;
+
+/// Create an error pointing at `range` (byte offsets into the value of `lit`).
+///
+/// Pointing at a part of a literal is only supported on nightly. On stable, the error points at
+/// the whole literal instead and the relevant line of the query is added to the message.
+pub fn error(lit: &LitStr, range: Range<usize>, message: &str) -> syn::Error {
+    match subspan(lit, range.clone()) {
+        Some(span) => syn::Error::new(span, message),
+        None => {
+            let (first_line, rest) = message.split_once('\n').unwrap_or((message, ""));
+            let mut message = first_line.to_string();
+            write_excerpt(&mut message, &lit.value(), range);
+            if !rest.is_empty() {
+                message.push('\n');
+                message.push_str(rest);
+            }
+            syn::Error::new(lit.span(), message)
+        }
+    }
+}
+
+/// The span of `range` (byte offsets into the value of `lit`), if supported by the compiler.
+pub fn subspan(lit: &LitStr, range: Range<usize>) -> Option<Span> {
+    let offsets = source_offsets(lit);
+    let start = *offsets.get(range.start)?;
+    let end = *offsets.get(range.end)?;
+    lit.token().subspan(start..end)
+}
+
+/// Set the span of all tokens in `stream` to `span`.
+pub fn respan(stream: TokenStream, span: Span) -> TokenStream {
+    stream
+        .into_iter()
+        .map(|tt| match tt {
+            TokenTree::Group(group) => {
+                let mut group = Group::new(group.delimiter(), respan(group.stream(), span));
+                group.set_span(span);
+                TokenTree::Group(group)
+            }
+            mut tt => {
+                tt.set_span(span);
+                tt
+            }
+        })
+        .collect()
+}
+
+/// Maps each byte offset of the literal's value (plus its end) to the corresponding byte offset in
+/// the literal's source (which includes the quotes and escape sequences).
+fn source_offsets(lit: &LitStr) -> Vec<usize> {
+    let source = lit.token().to_string();
+    let mut offsets = Vec::with_capacity(source.len());
+
+    // raw strings, e.g. r#"..."#
+    if source.starts_with('r') {
+        let start = source.find('"').map(|i| i + 1).unwrap_or_default();
+        let end = source.rfind('"').unwrap_or(source.len());
+        offsets.extend(start..=end);
+        return offsets;
+    }
+
+    let mut chars = source.char_indices().skip(1).peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == '"' {
+            offsets.push(i);
+            break;
+        }
+        if c != '\\' {
+            offsets.extend(std::iter::repeat_n(i, c.len_utf8()));
+            continue;
+        }
+
+        let Some((_, escaped)) = chars.next() else {
+            break;
+        };
+        let len = match escaped {
+            // line continuation: skip the line break and leading whitespace of the next line
+            '\n' | '\r' => {
+                while chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+                0
+            }
+            'x' => {
+                chars.nth(1);
+                1
+            }
+            'u' => {
+                let mut hex = String::new();
+                for (_, c) in chars.by_ref() {
+                    match c {
+                        '{' => {}
+                        '}' => break,
+                        c => hex.push(c),
+                    }
+                }
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map_or(1, char::len_utf8)
+            }
+            _ => 1,
+        };
+        offsets.extend(std::iter::repeat_n(i, len));
+    }
+    offsets
+}
+
+/// Append the line of `query` containing `range`, with the range underlined.
+fn write_excerpt(out: &mut String, query: &str, range: Range<usize>) {
+    let line_start = query[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = query[range.start..]
+        .find('\n')
+        .map_or(query.len(), |i| range.start + i);
+    let line = &query[line_start..line_end];
+    let indent = query[line_start..range.start].chars().count();
+    let width = query[range.start..range.end.min(line_end)]
+        .chars()
+        .count()
+        .max(1);
+
+    write!(out, "\n{line}\n{:indent$}{:^<width$}", "", "").unwrap();
+}
@@ -2,11 +2,14 @@ use std::str::FromStr;
 
 use proc_macro2::TokenStream;
 use quote::quote;
-use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
-use syn::{Attribute, Data, DataEnum, DeriveInput, Error, LitStr, Path, Type, parse_quote, token};
+use syn::{
+    Attribute, Data, DataEnum, DeriveInput, Error, Fields, Generics, Ident, Type, parse_quote,
+    token,
+};
 
 use crate::const_name;
+use crate::options::OptionExpr;
 use crate::rename::RenameAll;
 
 pub fn expand_derive_enum(input: DeriveInput) -> syn::Result<TokenStream> {
@@ -25,16 +28,12 @@ pub fn expand_derive_enum(input: DeriveInput) -> syn::Result<TokenStream> {
         ));
     };
 
-    let opts = extract_options(&attrs)?;
+    let opts = extract_options(&attrs, "postgres", UnknownOptions::Ignore, |_| Ok(false))?;
 
-    let mut new_generics = generics.clone();
-    new_generics.params.push(parse_quote!(Cols));
-    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
-
-    let mut variants = variants
+    let variants = variants
         .into_iter()
         .map(|v| {
-            let vopts = extract_variant_options(&v.attrs)?;
+            let vopts = extract_variant_options(&v.attrs, "postgres", UnknownOptions::Ignore)?;
             let name = v.ident.to_string();
             let name = if let Some(rename) = vopts.rename {
                 rename
@@ -46,43 +45,260 @@ pub fn expand_derive_enum(input: DeriveInput) -> syn::Result<TokenStream> {
             Ok(name)
         })
         .collect::<Result<Vec<_>, Error>>()?;
+
+    let type_name = opts.name.unwrap_or_else(|| ident.to_string());
+    let schema = extract_schema(&attrs)?;
+    Ok(expand_sql_type(
+        &ident, &generics, &schema, &type_name, variants,
+    ))
+}
+
+pub fn expand_derive_pg_enum(input: DeriveInput) -> syn::Result<TokenStream> {
+    let DeriveInput {
+        attrs,
+        vis,
+        ident,
+        generics,
+        data,
+    } = input;
+
+    let Data::Enum(DataEnum { variants, .. }) = data else {
+        return Err(Error::new(
+            ident.span(),
+            "PgEnum can only be derived from enums",
+        ));
+    };
+    if !generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            generics,
+            "PgEnum cannot be derived for generic enums",
+        ));
+    }
+
+    let opts = extract_pg_enum_options(&attrs)?;
+    let type_name = opts.name.unwrap_or_else(|| ident.to_string());
+    let schema = opts.schema.unwrap_or_else(|| DEFAULT_SCHEMA.to_string());
+
+    let mut idents = Vec::with_capacity(variants.len());
+    let mut names = Vec::with_capacity(variants.len());
+    for v in variants {
+        if !matches!(v.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                v,
+                "PgEnum can only be derived from enums with unit variants",
+            ));
+        }
+
+        let vopts = extract_variant_options(&v.attrs, "sqlm", UnknownOptions::Error)?;
+        let name = v.ident.to_string();
+        let name = if let Some(rename) = vopts.rename {
+            rename
+        } else if let Some(rename_all) = &opts.rename_all {
+            rename_all.apply(&name)
+        } else {
+            name
+        };
+        idents.push(v.ident);
+        names.push(name);
+    }
+
+    let sql_type = if opts.text {
+        quote! {
+            #[automatically_derived]
+            impl ::sqlm_postgres::SqlType for #ident {
+                type Type = String;
+            }
+        }
+    } else {
+        expand_sql_type(&ident, &generics, &schema, &type_name, names.clone())
+    };
+    let variant_count = names.len();
+    let to_str = quote! {
+        match self {
+            #(Self::#idents => #names,)*
+        }
+    };
+
+    let as_str = opts.as_str.then(|| {
+        quote! {
+            #[automatically_derived]
+            impl #ident {
+                /// The name of the variant as stored in Postgres.
+                #vis fn as_str(&self) -> &'static str {
+                    #to_str
+                }
+            }
+        }
+    });
+    let from_str = opts.from_str.then(|| {
+        quote! {
+            #[automatically_derived]
+            impl ::std::str::FromStr for #ident {
+                type Err = ::sqlm_postgres::types::InvalidVariant;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    match s {
+                        #(#names => Ok(Self::#idents),)*
+                        s => Err(::sqlm_postgres::types::InvalidVariant::new(#type_name, s)),
+                    }
+                }
+            }
+        }
+    });
+    let display = opts.display.then(|| {
+        quote! {
+            #[automatically_derived]
+            impl ::std::fmt::Display for #ident {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    f.write_str(#to_str)
+                }
+            }
+        }
+    });
+
+    let (from_sql_accepts, to_sql_accepts) = if opts.text {
+        (
+            quote! {
+                fn accepts(ty: &::sqlm_postgres::tokio_postgres::types::Type) -> bool {
+                    <&str as ::sqlm_postgres::FromSql<'_>>::accepts(ty)
+                }
+            },
+            quote! {
+                fn accepts(ty: &::sqlm_postgres::tokio_postgres::types::Type) -> bool {
+                    <&str as ::sqlm_postgres::ToSql>::accepts(ty)
+                }
+            },
+        )
+    } else {
+        let accepts = quote! {
+            fn accepts(ty: &::sqlm_postgres::tokio_postgres::types::Type) -> bool {
+                if ty.name() != #type_name || ty.schema() != #schema {
+                    return false;
+                }
+
+                match ty.kind() {
+                    ::sqlm_postgres::tokio_postgres::types::Kind::Enum(variants) => {
+                        variants.len() == #variant_count
+                            && variants.iter().all(|v| matches!(v.as_str(), #(#names)|*))
+                    }
+                    _ => false,
+                }
+            }
+        };
+        (accepts.clone(), accepts)
+    };
+
+    Ok(quote! {
+        #sql_type
+
+        #[automatically_derived]
+        impl<'a> ::sqlm_postgres::FromSql<'a> for #ident {
+            fn from_sql(
+                _: &::sqlm_postgres::tokio_postgres::types::Type,
+                raw: &'a [u8],
+            ) -> Result<Self, Box<dyn ::std::error::Error + Sync + Send>> {
+                match ::std::str::from_utf8(raw)? {
+                    #(#names => Ok(Self::#idents),)*
+                    s => Err(::sqlm_postgres::types::InvalidVariant::new(#type_name, s).into()),
+                }
+            }
+
+            #from_sql_accepts
+        }
+
+        #[automatically_derived]
+        impl ::sqlm_postgres::ToSql for #ident {
+            fn to_sql(
+                &self,
+                _: &::sqlm_postgres::tokio_postgres::types::Type,
+                out: &mut ::sqlm_postgres::internal::BytesMut,
+            ) -> Result<::sqlm_postgres::tokio_postgres::types::IsNull, Box<dyn ::std::error::Error + Sync + Send>> {
+                let s: &str = #to_str;
+                out.extend_from_slice(s.as_bytes());
+                Ok(::sqlm_postgres::tokio_postgres::types::IsNull::No)
+            }
+
+            #to_sql_accepts
+
+            ::sqlm_postgres::tokio_postgres::types::to_sql_checked!();
+        }
+
+        #as_str
+        #from_str
+        #display
+    })
+}
+
+fn expand_sql_type(
+    ident: &Ident,
+    generics: &Generics,
+    schema: &str,
+    type_name: &str,
+    mut variants: Vec<String>,
+) -> TokenStream {
     variants.sort();
 
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
     let mut enum_variants: Vec<Type> = Vec::with_capacity(variants.len());
     for name in variants {
         let name = const_name(&name);
         enum_variants.push(parse_quote!(::sqlm_postgres::types::EnumVariant<#name>));
     }
 
-    let enum_struct = quote! { ::sqlm_postgres::types::Enum<(#(#enum_variants,)*)> };
-    Ok(quote! {
+    let qualified_name = format!("{schema}.{type_name}");
+    let name = const_name(&qualified_name);
+    let enum_struct = quote! { ::sqlm_postgres::types::Enum<#name, (#(#enum_variants,)*)> };
+    quote! {
         #[automatically_derived]
         impl #impl_generics ::sqlm_postgres::SqlType for #ident #ty_generics #where_clause {
             type Type = #enum_struct;
         }
-    })
+    }
+}
+
+/// The schema enum types are assumed to be in if not set explicitly via `#[sqlm(schema = "...")]`.
+const DEFAULT_SCHEMA: &str = "public";
+
+/// How options a derive does not know about are treated.
+#[derive(Clone, Copy)]
+enum UnknownOptions {
+    /// Reject them, as the attribute belongs to this derive only (`#[sqlm(...)]`).
+    Error,
+    /// Ignore them, as they might be part of the FromSql/ToSql derive (`#[postgres(...)]`).
+    Ignore,
 }
 
 #[derive(Default)]
 struct Options {
+    name: Option<String>,
     rename_all: Option<RenameAll>,
 }
 
-fn extract_options(attrs: &[Attribute]) -> Result<Options, Error> {
+/// Extracts the `name` and `rename_all` options of the `#[<path>(...)]` attributes. Any other
+/// option is passed to `other`, which returns whether it handled it.
+fn extract_options(
+    attrs: &[Attribute],
+    path: &str,
+    unknown: UnknownOptions,
+    mut other: impl FnMut(&OptionExpr) -> Result<bool, Error>,
+) -> Result<Options, Error> {
     let mut opts = Options::default();
 
     for attr in attrs {
-        if !attr.path().is_ident("postgres") {
+        if !attr.path().is_ident(path) {
             continue;
         }
 
         for opt in attr.parse_args_with(Punctuated::<OptionExpr, token::Comma>::parse_terminated)? {
-            if opt.key.is_ident("rename_all") {
+            if opt.key.is_ident("name") {
                 let Some(value) = opt.value else {
-                    return Err(Error::new_spanned(
-                        opt.value,
-                        "rename_all must have a value",
-                    ));
+                    return Err(Error::new_spanned(opt.key, "name must have a value"));
+                };
+
+                opts.name = Some(value.value());
+            } else if opt.key.is_ident("rename_all") {
+                let Some(value) = opt.value else {
+                    return Err(Error::new_spanned(opt.key, "rename_all must have a value"));
                 };
 
                 let Ok(rename_all) = RenameAll::from_str(&value.value()) else {
@@ -90,58 +306,112 @@ fn extract_options(attrs: &[Attribute]) -> Result<Options, Error> {
                 };
 
                 opts.rename_all = Some(rename_all);
+            } else if !other(&opt)? && matches!(unknown, UnknownOptions::Error) {
+                return Err(Error::new_spanned(opt.key, "unknown option"));
             }
-
-            // ignore unknown options as they might be part of the FromSql/ToSql derive
         }
     }
 
     Ok(opts)
 }
 
+/// The schema set via `#[sqlm(schema = "...")]` (the only `sqlm` option of the `Enum` derive).
+fn extract_schema(attrs: &[Attribute]) -> Result<String, Error> {
+    let mut schema = None;
+
+    for attr in attrs {
+        if !attr.path().is_ident("sqlm") {
+            continue;
+        }
+
+        for opt in attr.parse_args_with(Punctuated::<OptionExpr, token::Comma>::parse_terminated)? {
+            if opt.key.is_ident("schema") {
+                let Some(value) = opt.value else {
+                    return Err(Error::new_spanned(opt.key, "schema must have a value"));
+                };
+
+                schema = Some(value.value());
+            } else {
+                return Err(Error::new_spanned(opt.key, "unknown option"));
+            }
+        }
+    }
+
+    Ok(schema.unwrap_or_else(|| DEFAULT_SCHEMA.to_string()))
+}
+
 #[derive(Default)]
 struct VariantOptions {
     rename: Option<String>,
 }
 
-fn extract_variant_options(attrs: &[Attribute]) -> Result<VariantOptions, Error> {
+/// Extracts the `name` option of the `#[<path>(...)]` attributes of a variant.
+fn extract_variant_options(
+    attrs: &[Attribute],
+    path: &str,
+    unknown: UnknownOptions,
+) -> Result<VariantOptions, Error> {
     let mut opts = VariantOptions::default();
 
     for attr in attrs {
-        if !attr.path().is_ident("postgres") {
+        if !attr.path().is_ident(path) {
             continue;
         }
 
         for opt in attr.parse_args_with(Punctuated::<OptionExpr, token::Comma>::parse_terminated)? {
             if opt.key.is_ident("name") {
                 let Some(value) = opt.value else {
-                    return Err(Error::new_spanned(opt.value, "rename must have a value"));
+                    return Err(Error::new_spanned(opt.key, "name must have a value"));
                 };
 
                 opts.rename = Some(value.value());
+            } else if matches!(unknown, UnknownOptions::Error) {
+                return Err(Error::new_spanned(opt.key, "unknown option"));
             }
-
-            // ignore unknown options as they might be part of the FromSql/ToSql derive
         }
     }
 
     Ok(opts)
 }
 
-#[derive(Debug, Hash)]
-struct OptionExpr {
-    key: Path,
-    value: Option<LitStr>,
+#[derive(Default)]
+struct PgEnumOptions {
+    name: Option<String>,
+    schema: Option<String>,
+    rename_all: Option<RenameAll>,
+    as_str: bool,
+    from_str: bool,
+    display: bool,
+    text: bool,
 }
 
-impl Parse for OptionExpr {
-    fn parse(input: ParseStream) -> syn::Result<Self> {
-        let key = input.parse()?;
-        let value = if Option::<token::Eq>::parse(input)?.is_some() {
-            Some(input.parse()?)
-        } else {
-            None
-        };
-        Ok(OptionExpr { key, value })
-    }
+fn extract_pg_enum_options(attrs: &[Attribute]) -> Result<PgEnumOptions, Error> {
+    let mut opts = PgEnumOptions::default();
+
+    let Options { name, rename_all } =
+        extract_options(attrs, "sqlm", UnknownOptions::Error, |opt| {
+            if opt.key.is_ident("schema") {
+                let Some(value) = &opt.value else {
+                    return Err(Error::new_spanned(&opt.key, "schema must have a value"));
+                };
+
+                opts.schema = Some(value.value());
+            } else if opt.key.is_ident("as_str") {
+                opts.as_str = true;
+            } else if opt.key.is_ident("from_str") {
+                opts.from_str = true;
+            } else if opt.key.is_ident("display") {
+                opts.display = true;
+            } else if opt.key.is_ident("text") {
+                opts.text = true;
+            } else {
+                return Ok(false);
+            }
+
+            Ok(true)
+        })?;
+    opts.name = name;
+    opts.rename_all = rename_all;
+
+    Ok(opts)
 }
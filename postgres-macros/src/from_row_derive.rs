@@ -1,13 +1,15 @@
-use proc_macro2::TokenStream;
-use quote::quote;
-use syn::parse::{Parse, ParseStream};
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::{
-    Attribute, Data, DataStruct, DeriveInput, Error, Expr, Fields, Path, PathArguments, Type,
-    parse_quote, token,
+    Attribute, Data, DataStruct, DeriveInput, Error, Expr, Fields, GenericParam, Ident,
+    PathArguments, Type, WherePredicate, parse_quote, token,
 };
 
 use crate::const_name;
+use crate::options::OptionExpr;
 
 pub fn expand_derive_from_row(input: DeriveInput) -> syn::Result<TokenStream> {
     let DeriveInput {
@@ -31,25 +33,94 @@ pub fn expand_derive_from_row(input: DeriveInput) -> syn::Result<TokenStream> {
 
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    let mut struct_columns: Vec<Type> = Vec::with_capacity(fields.named.len());
+    let struct_name = ident.to_string();
+    // the struct's generics plus the type of the column, for the per-column traits below
+    let mut generics_with_column = generics.clone();
+    generics_with_column.params.push(parse_quote!(__C));
+    let (impl_generics_with_column, _, _) = generics_with_column.split_for_impl();
+    let where_predicates = where_clause.map(|w| &w.predicates);
+    let mut struct_columns = Vec::with_capacity(fields.named.len());
+    let mut column_checks = Vec::with_capacity(fields.named.len());
+    let mut column_bounds = Vec::with_capacity(fields.named.len());
     let mut field_assignments = Vec::with_capacity(fields.named.len());
+    let mut key = None;
 
     let mut fields = fields
         .named
         .into_iter()
-        .map(|f| (f.ident.as_ref().unwrap().to_string(), f))
+        .map(|f| (f.ident.as_ref().unwrap().unraw().to_string(), f))
+        .collect::<Vec<_>>();
+    // in declaration order, for `{Struct::*}` in queries
+    let column_names = fields
+        .iter()
+        .map(|(name, _)| MacroColumn {
+            name: name.clone(),
+            ..Default::default()
+        })
         .collect::<Vec<_>>();
     fields.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    let columns_note = format!(
+        "`{struct_name}` expects the columns {} (sorted by name)",
+        fields
+            .iter()
+            .map(|(name, _)| format!("`{name}`"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
 
     for (name, f) in fields {
         let opts = extract_field_options(&f.attrs)?;
         let ident = f.ident.as_ref().unwrap();
-        let (ty, kind) = extract_inner_type(&f.ty)?;
+        if let Some(span) = opts.key {
+            if key.is_some() {
+                return Err(Error::new(span, "only one field can be marked as key"));
+            }
+            key = Some((ident.clone(), f.ty.clone()));
+        }
+        let (inner_ty, kind) = extract_inner_type(&f.ty)?;
+        // `#[sqlm(json)]` reads the field as `Json<T>` and unwraps it afterwards
+        let ty: Type = if opts.json {
+            parse_quote!(::sqlm_postgres::types::Json<#inner_ty>)
+        } else {
+            inner_ty.clone()
+        };
+        let unwrap = opts.json.then(|| quote! { .map(|v| v.0) });
 
-        let name = const_name(&name);
-        struct_columns.push(parse_quote!(
-            ::sqlm_postgres::types::StructColumn<<#ty as ::sqlm_postgres::internal::AsSqlType>::SqlType, #name>
-        ));
+        // A trait per column, so that type errors name the expected or mistyped column. The
+        // columns are checked by position (both sides sorted by name), so if the query selects
+        // different columns, the error names the first one that doesn't line up.
+        let column_trait = format_ident!("Column_{}", name);
+        let type_trait = format_ident!("ColumnType_{}", name);
+        let column_message = format!("expected column `{name}`, found `{{Self}}`");
+        let column_label = format!("the query's columns don't match the fields of `{struct_name}`");
+        let type_message =
+            format!("column `{name}` doesn't match the type of `{struct_name}::{name}`");
+        let const_name = const_name(&name);
+        column_checks.push(quote! {
+            #[allow(non_camel_case_types)]
+            #[diagnostic::on_unimplemented(
+                message = #column_message,
+                label = #column_label,
+                note = #columns_note
+            )]
+            pub trait #column_trait #impl_generics #where_clause {}
+            impl #impl_generics_with_column #column_trait #ty_generics for ::sqlm_postgres::types::StructColumn<__C, #const_name>
+            where
+                __C: #type_trait #ty_generics,
+                #where_predicates
+            {}
+
+            #[allow(non_camel_case_types)]
+            #[diagnostic::on_unimplemented(
+                message = #type_message,
+                label = "the column is of type `{Self}`"
+            )]
+            pub trait #type_trait #impl_generics #where_clause {}
+            impl #impl_generics #type_trait #ty_generics for <#ty as ::sqlm_postgres::internal::AsSqlType>::SqlType #where_clause {}
+        });
+        let param = format_ident!("C{}", struct_columns.len());
+        column_bounds.push(quote! { #param: #column_trait #ty_generics });
+        struct_columns.push(param);
 
         // Forward only certain args
         let attrs = f
@@ -58,11 +129,10 @@ pub fn expand_derive_from_row(input: DeriveInput) -> syn::Result<TokenStream> {
             .filter(|a| a.path().is_ident("cfg"))
             .collect::<Vec<_>>();
 
-        let name = ident.to_string();
         match kind {
             Kind::Option => field_assignments.push(quote! {
                 #(#attrs)*
-                #ident: row.try_get(#name)?,
+                #ident: row.try_get::<_, Option<#ty>>(#name)? #unwrap,
             }),
             Kind::Other => {
                 let default = if let Some(default) = opts.default {
@@ -73,7 +143,7 @@ pub fn expand_derive_from_row(input: DeriveInput) -> syn::Result<TokenStream> {
                 field_assignments.push(quote! {
                     #(#attrs)*
                     #ident: {
-                        let v: Option<#ty> = row.try_get(#name)?;
+                        let v: Option<#inner_ty> = row.try_get::<_, Option<#ty>>(#name)? #unwrap;
                         #default
                     },
                 })
@@ -82,23 +152,146 @@ pub fn expand_derive_from_row(input: DeriveInput) -> syn::Result<TokenStream> {
     }
 
     let type_struct = quote! { ::sqlm_postgres::types::Struct<(#(#struct_columns,)*)> };
-    Ok(quote! {
-        #[automatically_derived]
-        impl #impl_generics ::sqlm_postgres::FromRow<#type_struct> for #ident #ty_generics #where_clause {
-            fn from_row(row: ::sqlm_postgres::Row<#type_struct>) -> Result<Self, ::sqlm_postgres::tokio_postgres::Error> {
-                Ok(Self {
-                    #(#field_assignments)*
-                })
+    let mut generics_with_columns = generics.clone();
+    generics_with_columns.params.extend(
+        struct_columns
+            .iter()
+            .map(|c| -> GenericParam { parse_quote!(#c) }),
+    );
+    generics_with_columns.make_where_clause().predicates.extend(
+        column_bounds
+            .iter()
+            .map(|b| -> WherePredicate { parse_quote!(#b) }),
+    );
+    let (impl_generics_with_columns, _, where_clause_with_columns) =
+        generics_with_columns.split_for_impl();
+
+    // `#[sqlm(key)]`, for collecting rows into a map. `MapValue` is implemented here (instead of
+    // generically for all `Keyed` structs), so that it doesn't overlap with the one for the value
+    // of two columns.
+    let keyed = key.map(|(field, ty)| {
+        let map_value = |cols: TokenStream, params: &[&str]| {
+            let mut generics = generics.clone();
+            generics.params.extend(
+                params
+                    .iter()
+                    .map(|param| GenericParam::Type(format_ident!("{param}").into())),
+            );
+            generics.make_where_clause().predicates.push(parse_quote! {
+                Self: ::sqlm_postgres::FromRow<::sqlm_postgres::types::Struct<__Cols>>
+            });
+            let (impl_generics, _, where_clause) = generics.split_for_impl();
+            quote! {
+                #[automatically_derived]
+                impl #impl_generics ::sqlm_postgres::internal::MapValue<#cols, #ty> for #ident #ty_generics #where_clause {
+                    fn from_row(row: ::sqlm_postgres::tokio_postgres::Row) -> Result<(#ty, Self), ::sqlm_postgres::Error> {
+                        let value = ::sqlm_postgres::FromRow::<::sqlm_postgres::types::Struct<__Cols>>::from_row(row.into())?;
+                        Ok((::sqlm_postgres::Keyed::key(&value), value))
+                    }
+                }
             }
+        };
+        let map_value_struct = map_value(
+            quote! { ::sqlm_postgres::types::Struct<__Cols> },
+            &["__Cols"],
+        );
+        let map_value_pair = map_value(
+            quote! { ::sqlm_postgres::types::Pair<__K, __V, __Cols> },
+            &["__K", "__V", "__Cols"],
+        );
+
+        quote! {
+            #[automatically_derived]
+            impl #impl_generics ::sqlm_postgres::Keyed for #ident #ty_generics #where_clause {
+                type Key = #ty;
+
+                fn key(&self) -> Self::Key {
+                    ::std::clone::Clone::clone(&self.#field)
+                }
+            }
+
+            #map_value_struct
+            #map_value_pair
         }
+    });
+
+    let columns_macro = columns_macro(
+        &format_ident!("{}{}", COLUMNS_MACRO_PREFIX, ident),
+        &column_names,
+    );
+    Ok(quote! {
+        const _: () = {
+            #(#column_checks)*
+
+            #[automatically_derived]
+            impl #impl_generics_with_columns ::sqlm_postgres::FromRow<#type_struct> for #ident #ty_generics #where_clause_with_columns {
+                fn from_row(row: ::sqlm_postgres::Row<#type_struct>) -> Result<Self, ::sqlm_postgres::Error> {
+                    Ok(Self {
+                        #(#field_assignments)*
+                    })
+                }
+            }
+        };
+
+        #keyed
+
+        #columns_macro
     })
 }
 
+/// A column (or field) passed on to `sql!` by the macro generated via [`columns_macro`].
+#[derive(Default)]
+pub(crate) struct MacroColumn {
+    pub(crate) name: String,
+    /// Whether the field is an `Option` (only set by `ToRow`, for `{value:set(Struct)}`).
+    pub(crate) optional: bool,
+    /// The Postgres type, if known (only set by `ToRow`, for `{rows:unnest(Struct)}`).
+    pub(crate) pg_type: Option<String>,
+}
+
+/// A hidden macro next to the struct (e.g. `__sqlm_columns_User` for `{User::*}`), used by `sql!`
+/// to expand `{Struct::*}` (and `{value:insert(Struct)}`, `{value:set(Struct)}` and
+/// `{rows:unnest(Struct)}` for `ToRow`, e.g. via `__sqlm_fields_User`). `sql!` resolves it
+/// relative to the path of the struct in the placeholder, see `sql::struct_macro_path`.
+///
+/// Columns are passed as `"name"`, followed by `?` if the field is an `Option` and by `: "type"`
+/// if its Postgres type is known.
+pub(crate) fn columns_macro(macro_ident: &Ident, columns: &[MacroColumn]) -> TokenStream {
+    let columns = columns.iter().map(|column| {
+        let name = &column.name;
+        let optional = column.optional.then(|| quote! { ? });
+        match &column.pg_type {
+            Some(ty) => quote! { #name #optional: #ty },
+            None => quote! { #name #optional },
+        }
+    });
+    quote! {
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        macro_rules! #macro_ident {
+            ($key:literal; $($input:tt)*) => {
+                ::sqlm_postgres::sql!(@columns $key [#(#columns),*] $($input)*)
+            };
+        }
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #macro_ident;
+    }
+}
+
+/// The prefix of the macro generated by `FromRow` for `{Struct::*}`.
+pub(crate) const COLUMNS_MACRO_PREFIX: &str = "__sqlm_columns_";
+
+/// The prefix of the macro generated by `ToRow` for `{value:insert(Struct)}` (and `set`, `unnest`),
+/// which differs from [`COLUMNS_MACRO_PREFIX`] so that a struct can derive both.
+pub(crate) const FIELDS_MACRO_PREFIX: &str = "__sqlm_fields_";
+
 pub(crate) enum Kind {
     Option,
     Other,
 }
 
+#[allow(clippy::collapsible_if)]
 pub(crate) fn extract_inner_type(ty: &Type) -> syn::Result<(&Type, Kind)> {
     if let Type::Path(p) = ty {
         if p.path.segments.len() != 1 {
@@ -123,6 +316,8 @@ pub(crate) fn extract_inner_type(ty: &Type) -> syn::Result<(&Type, Kind)> {
 #[derive(Default)]
 struct FieldOptions {
     default: Option<Expr>,
+    json: bool,
+    key: Option<Span>,
 }
 
 fn extract_field_options(attrs: &[Attribute]) -> Result<FieldOptions, Error> {
@@ -133,9 +328,19 @@ fn extract_field_options(attrs: &[Attribute]) -> Result<FieldOptions, Error> {
             continue;
         }
 
-        for opt in attr.parse_args_with(Punctuated::<OptionExpr, token::Comma>::parse_terminated)? {
+        for opt in
+            attr.parse_args_with(Punctuated::<OptionExpr<Expr>, token::Comma>::parse_terminated)?
+        {
             if opt.key.is_ident("default") {
-                opts.default = Some(opt.value);
+                let Some(value) = opt.value else {
+                    return Err(Error::new_spanned(opt.key, "default must have a value"));
+                };
+
+                opts.default = Some(value);
+            } else if opt.key.is_ident("json") {
+                opts.json = true;
+            } else if opt.key.is_ident("key") {
+                opts.key = Some(opt.key.span());
             } else {
                 return Err(Error::new_spanned(opt.key, "unknown option"));
             }
@@ -144,19 +349,3 @@ fn extract_field_options(attrs: &[Attribute]) -> Result<FieldOptions, Error> {
 
     Ok(opts)
 }
-
-#[derive(Debug, Hash)]
-struct OptionExpr {
-    key: Path,
-    value: Expr,
-}
-
-impl Parse for OptionExpr {
-    fn parse(input: ParseStream) -> syn::Result<Self> {
-        let key = input.parse()?;
-
-        token::Eq::parse(input)?;
-        let value = input.parse()?;
-        Ok(OptionExpr { key, value })
-    }
-}
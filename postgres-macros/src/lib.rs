@@ -1,11 +1,15 @@
 #![cfg_attr(nightly_column_names, feature(adt_const_params, unsized_const_params))]
 #![cfg_attr(nightly_column_names, allow(incomplete_features))]
 
+mod diagnostic;
 mod enum_derive;
 mod from_row_derive;
+mod newtype_derive;
+mod options;
 mod parser;
 mod rename;
 mod sql;
+mod to_row_derive;
 
 use proc_macro::TokenStream;
 use syn::parse_macro_input;
@@ -19,7 +23,16 @@ pub fn derive_fromsql(input: TokenStream) -> TokenStream {
         .into()
 }
 
-#[proc_macro_derive(Enum)]
+#[proc_macro_derive(ToRow, attributes(sqlm))]
+pub fn derive_to_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input);
+
+    to_row_derive::expand_derive_to_row(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(Enum, attributes(sqlm))]
 pub fn derive_enum(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input);
 
@@ -28,18 +41,50 @@ pub fn derive_enum(input: TokenStream) -> TokenStream {
         .into()
 }
 
+#[proc_macro_derive(PgEnum, attributes(sqlm))]
+pub fn derive_pg_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input);
+
+    enum_derive::expand_derive_pg_enum(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+#[proc_macro_derive(Newtype)]
+pub fn derive_newtype(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input);
+
+    newtype_derive::expand_derive_newtype(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
 #[proc_macro]
 pub fn sql(item: TokenStream) -> TokenStream {
     sql::sql(item)
 }
 
+/// Encode a column or variant name as a type, e.g. `N2<'i', 'd'>` for `id` (see
+/// `sqlm_postgres::types::N1` and following).
 #[cfg(not(nightly_column_names))]
-fn const_name(name: &str) -> usize {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-    let mut hasher = DefaultHasher::default();
-    name.hash(&mut hasher);
-    hasher.finish() as usize
+fn const_name(name: &str) -> proc_macro2::TokenStream {
+    use proc_macro2::Literal;
+    use quote::{format_ident, quote};
+
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut chunks = chars
+        .chunks(32)
+        .map(|chunk| {
+            let ident = format_ident!("N{}", chunk.len());
+            let chars = chunk.iter().map(|c| Literal::character(*c));
+            quote! { ::sqlm_postgres::types::#ident<#(#chars),*> }
+        })
+        .collect::<Vec<_>>();
+    if chunks.len() == 1 {
+        chunks.remove(0)
+    } else {
+        quote! { (#(#chunks,)*) }
+    }
 }
 
 #[cfg(nightly_column_names)]
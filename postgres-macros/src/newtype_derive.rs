@@ -0,0 +1,107 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DataStruct, DeriveInput, Error, Fields, GenericParam, Lifetime, LifetimeParam};
+
+pub fn expand_derive_newtype(input: DeriveInput) -> syn::Result<TokenStream> {
+    let DeriveInput {
+        attrs: _,
+        vis: _,
+        ident,
+        generics,
+        data,
+    } = input;
+
+    let Data::Struct(DataStruct {
+        fields: Fields::Unnamed(fields),
+        ..
+    }) = data
+    else {
+        return Err(Error::new(
+            ident.span(),
+            "Newtype can only be derived from tuple structs with a single field",
+        ));
+    };
+    if fields.unnamed.len() != 1 {
+        return Err(Error::new_spanned(
+            fields,
+            "Newtype can only be derived from tuple structs with a single field",
+        ));
+    }
+    let inner = &fields.unnamed[0].ty;
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut where_clause = where_clause
+        .cloned()
+        .unwrap_or_else(|| syn::parse_quote!(where));
+
+    let mut sql_type_where = where_clause.clone();
+    sql_type_where
+        .predicates
+        .push(syn::parse_quote!(#inner: ::sqlm_postgres::SqlType));
+
+    let mut to_sql_where = where_clause.clone();
+    to_sql_where
+        .predicates
+        .push(syn::parse_quote!(#inner: ::sqlm_postgres::ToSql));
+
+    let lifetime = Lifetime::new("'__sqlm", ident.span());
+    let mut from_sql_generics = generics.clone();
+    from_sql_generics.params.insert(
+        0,
+        GenericParam::Lifetime(LifetimeParam::new(lifetime.clone())),
+    );
+    let (from_sql_impl_generics, _, _) = from_sql_generics.split_for_impl();
+    where_clause
+        .predicates
+        .push(syn::parse_quote!(#inner: ::sqlm_postgres::FromSql<#lifetime>));
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::sqlm_postgres::SqlType for #ident #ty_generics #sql_type_where {
+            type Type = <#inner as ::sqlm_postgres::SqlType>::Type;
+        }
+
+        #[automatically_derived]
+        impl #from_sql_impl_generics ::sqlm_postgres::FromSql<#lifetime> for #ident #ty_generics #where_clause {
+            fn from_sql(
+                ty: &::sqlm_postgres::tokio_postgres::types::Type,
+                raw: &#lifetime [u8],
+            ) -> Result<Self, Box<dyn ::std::error::Error + Sync + Send>> {
+                <#inner as ::sqlm_postgres::FromSql<#lifetime>>::from_sql(ty, raw).map(Self)
+            }
+
+            fn from_sql_null(
+                ty: &::sqlm_postgres::tokio_postgres::types::Type,
+            ) -> Result<Self, Box<dyn ::std::error::Error + Sync + Send>> {
+                <#inner as ::sqlm_postgres::FromSql<#lifetime>>::from_sql_null(ty).map(Self)
+            }
+
+            fn accepts(ty: &::sqlm_postgres::tokio_postgres::types::Type) -> bool {
+                <#inner as ::sqlm_postgres::FromSql<#lifetime>>::accepts(ty)
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics ::sqlm_postgres::ToSql for #ident #ty_generics #to_sql_where {
+            fn to_sql(
+                &self,
+                ty: &::sqlm_postgres::tokio_postgres::types::Type,
+                out: &mut ::sqlm_postgres::internal::BytesMut,
+            ) -> Result<::sqlm_postgres::tokio_postgres::types::IsNull, Box<dyn ::std::error::Error + Sync + Send>> {
+                <#inner as ::sqlm_postgres::ToSql>::to_sql(&self.0, ty, out)
+            }
+
+            fn accepts(ty: &::sqlm_postgres::tokio_postgres::types::Type) -> bool {
+                <#inner as ::sqlm_postgres::ToSql>::accepts(ty)
+            }
+
+            fn to_sql_checked(
+                &self,
+                ty: &::sqlm_postgres::tokio_postgres::types::Type,
+                out: &mut ::sqlm_postgres::internal::BytesMut,
+            ) -> Result<::sqlm_postgres::tokio_postgres::types::IsNull, Box<dyn ::std::error::Error + Sync + Send>> {
+                <#inner as ::sqlm_postgres::ToSql>::to_sql_checked(&self.0, ty, out)
+            }
+        }
+    })
+}
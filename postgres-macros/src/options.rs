@@ -0,0 +1,21 @@
+use syn::parse::{Parse, ParseStream};
+use syn::{LitStr, Path, token};
+
+/// An option of a `#[sqlm(...)]` attribute, e.g. `name = "..."` or `json`.
+#[derive(Debug, Hash)]
+pub struct OptionExpr<V = LitStr> {
+    pub key: Path,
+    pub value: Option<V>,
+}
+
+impl<V: Parse> Parse for OptionExpr<V> {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key = input.parse()?;
+        let value = if Option::<token::Eq>::parse(input)?.is_some() {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(OptionExpr { key, value })
+    }
+}
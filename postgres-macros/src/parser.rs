@@ -1,36 +1,54 @@
-use ariadne::{Color, Label, Report, ReportKind, Source};
+use std::ops::Range;
+
 use chumsky::prelude::*;
 use chumsky::text::ident;
 
-pub fn parse(input: &str) -> Result<Vec<Token<'_>>, Rich<'_, char>> {
-    match parser().parse(input).into_result() {
-        Ok(tokens) => Ok(tokens),
-        Err(errors) => {
-            let err = errors.into_iter().next().unwrap();
-            Report::build(ReportKind::Error, err.span().into_range())
-                .with_message(err.to_string())
-                .with_label(
-                    Label::new(err.span().into_range())
-                        .with_message(err.reason().to_string())
-                        .with_color(Color::Red),
-                )
-                .finish()
-                .eprint(Source::from(input))
-                .unwrap();
-            Err(err)
-        }
-    }
+/// Parse the query into tokens, each with its byte range in the query.
+pub fn parse(input: &str) -> Result<Vec<(Token<'_>, Range<usize>)>, Rich<'_, char>> {
+    parser()
+        .parse(input)
+        .into_result()
+        .map_err(|errors| errors.into_iter().next().unwrap())
 }
 
-fn parser<'a>() -> impl Parser<'a, &'a str, Vec<Token<'a>>, extra::Err<Rich<'a, char>>> {
-    token_parser().repeated().collect().then_ignore(end())
+fn parser<'a>()
+-> impl Parser<'a, &'a str, Vec<(Token<'a>, Range<usize>)>, extra::Err<Rich<'a, char>>> {
+    token_parser()
+        .map_with(|token, e| (token, e.span().into_range()))
+        .repeated()
+        .collect()
+        .then_ignore(end())
 }
 
 pub enum Token<'a> {
     EscapedCurlyStart,
     EscapedCurlyEnd,
     Text(&'a str),
-    Argument(Argument<'a>),
+    /// A parameter, with an optional cast (e.g. `{x::int8}`) and modifier (e.g. `{x:secret}`).
+    Argument {
+        argument: Argument<'a>,
+        cast: Option<&'a str>,
+        modifier: Option<&'a str>,
+    },
+    /// The columns of a `FromRow` struct, e.g. `{User::*}` or `{u.User::*}`.
+    Columns {
+        alias: Option<&'a str>,
+        path: &'a str,
+    },
+    /// The fields of a `ToRow` struct, e.g. `{user:insert(User)}`, `{patch:set(UserPatch)}` or
+    /// `{rows:unnest(User)}`.
+    Row {
+        argument: Argument<'a>,
+        kind: RowKind,
+        path: &'a str,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RowKind {
+    Insert,
+    Set,
+    Unnest,
 }
 
 fn token_parser<'a>() -> impl Parser<'a, &'a str, Token<'a>, extra::Err<Rich<'a, char>>> {
@@ -53,28 +71,106 @@ fn token_parser<'a>() -> impl Parser<'a, &'a str, Token<'a>, extra::Err<Rich<'a,
             .at_least(1)
             .to_slice()
             .map(Token::Text),
-        // arguments: {}, {0}, {name}
+        // columns of a struct: {User::*}, {u.User::*}, {models::User::*}
+        just("{")
+            .ignore_then(ident().then_ignore(just(".")).or_not())
+            .then(
+                ident()
+                    .then_ignore(just("::"))
+                    .repeated()
+                    .at_least(1)
+                    .to_slice(),
+            )
+            .then_ignore(just("*}"))
+            .map(|(alias, path): (_, &str)| Token::Columns {
+                alias,
+                path: path.trim_end_matches("::"),
+            }),
+        // fields of a struct: {user:insert(User)}, {patch:set(UserPatch)}, {rows:unnest(User)}
         just("{")
             .ignore_then(argument_parser())
+            .then_ignore(just(":"))
+            .then(choice((
+                just("insert").to(RowKind::Insert),
+                just("set").to(RowKind::Set),
+                just("unnest").to(RowKind::Unnest),
+            )))
+            .then_ignore(just("("))
+            .then(ident().separated_by(just("::")).at_least(1).to_slice())
+            .then_ignore(just(")}"))
+            .map(|((argument, kind), path)| Token::Row {
+                argument,
+                kind,
+                path,
+            }),
+        // arguments: {}, {0}, {name}, {user.id}, optionally followed by a cast, e.g. {x::int8},
+        // and/or a modifier, e.g. {name:secret}
+        just("{")
+            .ignore_then(argument_parser())
+            .then(cast_parser().or_not())
+            .then(just(":").ignore_then(ident()).or_not())
             .then_ignore(just("}"))
-            .map(Token::Argument),
+            .map(|((argument, cast), modifier)| Token::Argument {
+                argument,
+                cast,
+                modifier,
+            }),
     ))
 }
 
+fn cast_parser<'a>() -> impl Parser<'a, &'a str, &'a str, extra::Err<Rich<'a, char>>> {
+    // anything up to the end of the placeholder (or a modifier), e.g. `int8[]`, `varchar(10)` or
+    // `double precision`
+    just("::").ignore_then(
+        none_of("{}:")
+            .repeated()
+            .at_least(1)
+            .to_slice()
+            .map(str::trim)
+            .filter(|ty: &&str| !ty.is_empty()),
+    )
+}
+
 pub enum Argument<'a> {
     Positional(usize),
     Next,
     Named(&'a str),
+    /// A variable followed by field accesses, indexing or method calls, e.g. `user.id`, `ids[0]`
+    /// or `name.trim()`.
+    Expr(&'a str),
 }
 
 fn argument_parser<'a>() -> impl Parser<'a, &'a str, Argument<'a>, extra::Err<Rich<'a, char>>> {
-    // none_of("{}").repeated().then(
     choice((
         text::int(10)
             .from_str()
             .unwrapped()
             .map(Argument::Positional),
+        expr_parser().map(Argument::Expr),
         ident().map(Argument::Named),
         empty().map(|_| Argument::Next),
     ))
 }
+
+fn expr_parser<'a>() -> impl Parser<'a, &'a str, &'a str, extra::Err<Rich<'a, char>>> {
+    // the contents of `(...)` and `[...]`, which may contain further (balanced) brackets
+    let group = recursive(|group| {
+        choice((
+            none_of("()[]{}").ignored(),
+            group.clone().delimited_by(just("("), just(")")),
+            group.delimited_by(just("["), just("]")),
+        ))
+        .repeated()
+    });
+
+    // .field, .0, .method(...), [...]
+    let postfix = choice((
+        just(".")
+            .then(choice((ident().ignored(), text::int(10).ignored())))
+            .then(group.clone().delimited_by(just("("), just(")")).or_not())
+            .ignored(),
+        group.delimited_by(just("["), just("]")),
+    ));
+
+    ident().then(postfix.repeated().at_least(1)).to_slice()
+}
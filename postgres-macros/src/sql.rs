@@ -1,23 +1,27 @@
 use std::collections::HashMap;
 use std::collections::hash_map::Entry;
 use std::fmt::Write;
+use std::ops::Range;
 use std::str::FromStr;
 use std::sync::Arc;
 
 use postgres::Config;
 use postgres::config::SslMode;
+use postgres::error::ErrorPosition;
 use proc_macro::TokenStream;
-use quote::{ToTokens, format_ident, quote};
+use quote::{ToTokens, format_ident, quote, quote_spanned};
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::token::Comma;
-use syn::{Expr, LitStr, Type, parse_macro_input, parse_quote};
+use syn::{Expr, LitStr, parse_macro_input};
 
-use crate::const_name;
-use crate::parser::{self, Argument, Token};
+use crate::from_row_derive::{COLUMNS_MACRO_PREFIX, FIELDS_MACRO_PREFIX};
+use crate::parser::{self, Argument, RowKind, Token};
+use crate::{const_name, diagnostic};
 
 pub fn sql(item: TokenStream) -> TokenStream {
+    let raw_input = proc_macro2::TokenStream::from(item.clone());
     let input = parse_macro_input!(item as Input);
     // dbg!(&input);
 
@@ -32,6 +36,7 @@ pub fn sql(item: TokenStream) -> TokenStream {
                     Parameter {
                         expr: expr_assign.right,
                         index: None,
+                        used: false,
                     },
                 );
             } else {
@@ -47,6 +52,7 @@ pub fn sql(item: TokenStream) -> TokenStream {
                 unnamed_arguments.push(Parameter {
                     expr: Box::new(expr),
                     index: None,
+                    used: false,
                 });
             }
         }
@@ -56,17 +62,37 @@ pub fn sql(item: TokenStream) -> TokenStream {
     let query = input.query.value();
     let mut result = String::with_capacity(query.len());
     let mut parameters = Vec::new();
+    let mut secret_parameters = Vec::new();
+    let mut unnest_parameters = Vec::new();
+    // The arguments of `{value:insert(Struct)}` etc. (and the bindings they are matched to), so
+    // that each is evaluated once instead of once per field.
+    let mut row_arguments = Vec::new();
+    let mut row_parameters = Vec::new();
 
     let tokens = match parser::parse(&query) {
         Ok(tokens) => tokens,
         Err(err) => {
-            return syn::Error::new(input.query.span(), err)
+            return diagnostic::error(&input.query, err.span().into_range(), &err.to_string())
                 .into_compile_error()
                 .into();
         }
     };
 
-    for token in tokens {
+    // Maps the parts of the rewritten query back to the original query, so that errors reported
+    // for the rewritten query can point at the original one.
+    let mut segments = Vec::new();
+
+    for (token, range) in tokens {
+        segments.push(Segment {
+            offset: result.len(),
+            range: range.clone(),
+            text: matches!(token, Token::Text(_)),
+        });
+
+        let (secret, cast) = match &token {
+            Token::Argument { cast, modifier, .. } => (*modifier == Some("secret"), *cast),
+            _ => (false, None),
+        };
         let index = match token {
             Token::EscapedCurlyStart => {
                 result.push('{');
@@ -80,11 +106,217 @@ pub fn sql(item: TokenStream) -> TokenStream {
                 result.push_str(text);
                 continue;
             }
-            Token::Argument(Argument::Next) => {
+            Token::Columns { alias, path } => {
+                let macro_path = struct_macro_path(path, COLUMNS_MACRO_PREFIX);
+                let Some((_, columns)) = input
+                    .columns
+                    .iter()
+                    .find(|(key, _)| key.value() == macro_path)
+                else {
+                    // The column names are provided by the `FromRow` derive of the struct, via a
+                    // hidden macro next to it that calls `sql!` again with the columns prepended.
+                    return expand_columns(&input.query, range, &macro_path, raw_input).into();
+                };
+                for (i, column) in columns.iter().enumerate() {
+                    if i > 0 {
+                        result.push_str(", ");
+                    }
+                    if let Some(alias) = alias {
+                        write!(result, "{alias}.").unwrap();
+                    }
+                    write!(result, "\"{}\"", column.name.value().replace('"', "\"\"")).unwrap();
+                }
+                continue;
+            }
+            Token::Row {
+                argument,
+                kind,
+                path,
+            } => {
+                let macro_path = struct_macro_path(path, FIELDS_MACRO_PREFIX);
+                let Some((_, fields)) = input
+                    .columns
+                    .iter()
+                    .find(|(key, _)| key.value() == macro_path)
+                else {
+                    // Same as for `{User::*}`, but the macro is generated by the `ToRow` derive.
+                    return expand_columns(&input.query, range, &macro_path, raw_input).into();
+                };
+
+                let (expr, span) = match argument {
+                    Argument::Next | Argument::Positional(_) => {
+                        let ix = if let Argument::Positional(ix) = argument {
+                            ix
+                        } else {
+                            next_arg += 1;
+                            next_arg - 1
+                        };
+                        let Some(param) = unnamed_arguments.get_mut(ix) else {
+                            return diagnostic::error(
+                                &input.query,
+                                range,
+                                &format!("missing argument for position {ix}"),
+                            )
+                            .into_compile_error()
+                            .into();
+                        };
+                        param.used = true;
+                        (param.expr.to_token_stream(), param.expr.span())
+                    }
+                    Argument::Named(ident) => {
+                        if let Some(param) = named_arguments.get_mut(ident) {
+                            param.used = true;
+                            (param.expr.to_token_stream(), param.expr.span())
+                        } else {
+                            let span = diagnostic::subspan(&input.query, range.clone())
+                                .unwrap_or_else(|| input.query.span());
+                            let ident = format_ident!("{}", ident, span = span);
+                            (ident.to_token_stream(), span)
+                        }
+                    }
+                    Argument::Expr(expr) => {
+                        match placeholder_expr(
+                            &input.query,
+                            range.clone(),
+                            expr,
+                            &mut named_arguments,
+                        ) {
+                            Ok(expr) => expr,
+                            Err(err) => return err.into_compile_error().into(),
+                        }
+                    }
+                };
+                let Ok(struct_path) = syn::parse_str::<syn::Path>(path) else {
+                    return diagnostic::error(
+                        &input.query,
+                        range,
+                        &format!("invalid struct path `{path}`"),
+                    )
+                    .into_compile_error()
+                    .into();
+                };
+                let struct_path = diagnostic::respan(struct_path.into_token_stream(), span);
+
+                let binding = proc_macro2::Ident::new(
+                    &format!("__sqlm_row_{}", row_arguments.len()),
+                    span.resolved_at(proc_macro2::Span::mixed_site()),
+                );
+                row_arguments.push((binding.clone(), quote_spanned! {span=> &(#expr) }));
+
+                // Each field becomes its own parameter. `to_row` (and `unnest`) make sure that the
+                // argument actually is the struct the field names were taken from.
+                let mut indices = Vec::with_capacity(fields.len());
+                for field in fields {
+                    let field = field_ident(&field.name.value(), span);
+                    let param = if kind == RowKind::Unnest {
+                        unnest_parameters.push(parameters.len() + 1);
+                        quote_spanned! {span=>
+                            ::sqlm_postgres::internal::unnest::<#struct_path, _>(
+                                #binding,
+                                |row| &row.#field,
+                            )
+                        }
+                    } else {
+                        quote_spanned! {span=>
+                            ::sqlm_postgres::internal::to_row::<#struct_path>(#binding).#field
+                        }
+                    };
+                    parameters.push((param, span));
+                    indices.push(parameters.len());
+                }
+                row_parameters.extend(&indices);
+
+                let quote = |name: &str| format!("\"{}\"", name.replace('"', "\"\""));
+                match kind {
+                    RowKind::Insert => {
+                        let names = fields.iter().map(|field| quote(&field.name.value()));
+                        let placeholders = indices.iter().map(|index| format!("${index}"));
+                        write!(
+                            result,
+                            "({}) VALUES ({})",
+                            names.collect::<Vec<_>>().join(", "),
+                            placeholders.collect::<Vec<_>>().join(", ")
+                        )
+                        .unwrap();
+                    }
+                    RowKind::Set => {
+                        // A `None` keeps the current value, which keeps the query itself static
+                        // (and thus checked at compile time). In turn, columns cannot be set to
+                        // `NULL` this way.
+                        for (i, (field, index)) in fields.iter().zip(&indices).enumerate() {
+                            if i > 0 {
+                                result.push_str(", ");
+                            }
+                            let name = quote(&field.name.value());
+                            if field.optional {
+                                write!(result, "{name} = COALESCE(${index}, {name})").unwrap();
+                            } else {
+                                write!(result, "{name} = ${index}").unwrap();
+                            }
+                        }
+                    }
+                    RowKind::Unnest => {
+                        // Postgres cannot infer the types of the arrays passed to `UNNEST`.
+                        let mut arrays = Vec::with_capacity(fields.len());
+                        for (field, index) in fields.iter().zip(&indices) {
+                            let Some(ty) = &field.ty else {
+                                return diagnostic::error(
+                                    &input.query,
+                                    range,
+                                    &format!(
+                                        "unknown Postgres type of `{path}::{}`, set it via \
+                                         `#[sqlm(pg_type = \"...\")]`",
+                                        field.name.value()
+                                    ),
+                                )
+                                .into_compile_error()
+                                .into();
+                            };
+                            arrays.push(format!("${index}::{}[]", ty.value()));
+                        }
+                        write!(result, "SELECT * FROM UNNEST({})", arrays.join(", ")).unwrap();
+                    }
+                }
+                continue;
+            }
+            Token::Argument {
+                modifier: Some(modifier @ ("insert" | "set" | "unnest")),
+                ..
+            } => {
+                return diagnostic::error(
+                    &input.query,
+                    range,
+                    &format!(
+                        "`{modifier}` requires the struct deriving `ToRow`, e.g. `{{user:{modifier}(User)}}`"
+                    ),
+                )
+                .into_compile_error()
+                .into();
+            }
+            Token::Argument {
+                modifier: Some(modifier),
+                ..
+            } if modifier != "secret" => {
+                return diagnostic::error(
+                    &input.query,
+                    range,
+                    &format!(
+                        "unknown placeholder modifier `{modifier}` (expected `secret`, casts are \
+                         written as `{{x::type}}`)"
+                    ),
+                )
+                .into_compile_error()
+                .into();
+            }
+            Token::Argument {
+                argument: Argument::Next,
+                ..
+            } => {
                 let Some(param) = unnamed_arguments.get_mut(next_arg) else {
-                    return syn::Error::new(
-                        input.query.span(),
-                        format!("missing argument for position {next_arg}"),
+                    return diagnostic::error(
+                        &input.query,
+                        range,
+                        &format!("missing argument for position {next_arg}"),
                     )
                     .into_compile_error()
                     .into();
@@ -93,17 +325,21 @@ pub fn sql(item: TokenStream) -> TokenStream {
                 if let Some(index) = param.index {
                     index
                 } else {
-                    parameters.push(param.expr.to_token_stream());
+                    parameters.push((param.expr.to_token_stream(), param.expr.span()));
                     let index = parameters.len();
                     param.index = Some(index);
                     index
                 }
             }
-            Token::Argument(Argument::Positional(ix)) => {
+            Token::Argument {
+                argument: Argument::Positional(ix),
+                ..
+            } => {
                 let Some(param) = unnamed_arguments.get_mut(ix) else {
-                    return syn::Error::new(
-                        input.query.span(),
-                        format!("missing argument for index {ix}"),
+                    return diagnostic::error(
+                        &input.query,
+                        range,
+                        &format!("missing argument for index {ix}"),
                     )
                     .into_compile_error()
                     .into();
@@ -111,18 +347,21 @@ pub fn sql(item: TokenStream) -> TokenStream {
                 if let Some(index) = param.index {
                     index
                 } else {
-                    parameters.push(param.expr.to_token_stream());
+                    parameters.push((param.expr.to_token_stream(), param.expr.span()));
                     let index = parameters.len();
                     param.index = Some(index);
                     index
                 }
             }
-            Token::Argument(Argument::Named(ident)) => {
+            Token::Argument {
+                argument: Argument::Named(ident),
+                ..
+            } => {
                 if let Some(param) = named_arguments.get_mut(ident) {
                     if let Some(index) = param.index {
                         index
                     } else {
-                        parameters.push(param.expr.to_token_stream());
+                        parameters.push((param.expr.to_token_stream(), param.expr.span()));
                         let index = parameters.len();
                         param.index = Some(index);
                         index
@@ -131,8 +370,10 @@ pub fn sql(item: TokenStream) -> TokenStream {
                     match variable_arguments.entry(ident) {
                         Entry::Occupied(e) => *e.get(),
                         Entry::Vacant(e) => {
-                            let ident = format_ident!("{}", ident);
-                            parameters.push(ident.to_token_stream());
+                            let span = diagnostic::subspan(&input.query, range)
+                                .unwrap_or_else(|| input.query.span());
+                            let ident = format_ident!("{}", ident, span = span);
+                            parameters.push((ident.to_token_stream(), span));
                             let index = parameters.len();
                             e.insert(index);
                             index
@@ -140,16 +381,38 @@ pub fn sql(item: TokenStream) -> TokenStream {
                     }
                 }
             }
+            Token::Argument {
+                argument: Argument::Expr(expr),
+                ..
+            } => match variable_arguments.entry(expr) {
+                Entry::Occupied(e) => *e.get(),
+                Entry::Vacant(e) => {
+                    match placeholder_expr(&input.query, range, expr, &mut named_arguments) {
+                        Ok(expr) => parameters.push(expr),
+                        Err(err) => return err.into_compile_error().into(),
+                    }
+                    let index = parameters.len();
+                    e.insert(index);
+                    index
+                }
+            },
         };
 
+        if secret {
+            secret_parameters.push(index);
+        }
         write!(result, "${}", index).unwrap();
+        if let Some(cast) = cast {
+            // e.g. `{x::int8}`, which also determines the type the parameter is checked against
+            write!(result, "::{cast}").unwrap();
+        }
     }
 
     for arg in unnamed_arguments
         .into_iter()
         .chain(named_arguments.into_values())
     {
-        if arg.index.is_none() {
+        if arg.index.is_none() && !arg.used {
             return syn::Error::new(arg.expr.span(), "argument never used")
                 .into_compile_error()
                 .into();
@@ -202,29 +465,41 @@ pub fn sql(item: TokenStream) -> TokenStream {
 
     let stmt = match client.prepare(&result) {
         Ok(stmt) => stmt,
-        Err(err) => {
-            return syn::Error::new(input.query.span(), format!("query failed: {err}"))
-                .into_compile_error()
-                .into();
-        }
+        Err(err) => return query_error(&input.query, &result, &segments, err).into(),
     };
 
-    let mut typed_parameters = Vec::with_capacity(parameters.len());
-    for (ty, param) in stmt.params().iter().zip(parameters) {
-        if let Some((is_array, variants)) = enum_type(ty) {
-            let mut enum_variants: Vec<Type> = Vec::with_capacity(variants.len());
-            for variant in variants {
-                let name = const_name(&variant);
-                enum_variants.push(parse_quote!(::sqlm_postgres::types::EnumVariant<#name>));
+    // If there are row arguments, the other arguments are bound as well (see `parameters` below).
+    let mut arguments = row_arguments;
+    if !arguments.is_empty() {
+        for (i, (param, span)) in parameters.iter_mut().enumerate() {
+            if row_parameters.contains(&(i + 1)) {
+                continue;
             }
+            let binding = proc_macro2::Ident::new(
+                &format!("__sqlm_param_{i}"),
+                span.resolved_at(proc_macro2::Span::mixed_site()),
+            );
+            arguments.push((binding.clone(), quote_spanned! {*span=> &(#param) }));
+            *param = quote_spanned! {*span=> (*#binding) };
+        }
+    }
 
-            let enum_struct = quote! { ::sqlm_postgres::types::Enum<(#(#enum_variants,)*)> };
-            if is_array {
-                typed_parameters.push(quote! {
+    let mut typed_parameters = Vec::with_capacity(parameters.len());
+    for (ty, (param, span)) in stmt.params().iter().zip(parameters) {
+        if let Some((is_array, enum_struct)) = enum_type(ty) {
+            // Point type errors at the argument instead of the whole macro invocation.
+            let enum_struct = diagnostic::respan(enum_struct, span);
+            if unnest_parameters.contains(&(typed_parameters.len() + 1)) {
+                // `Unnest` is not a slice, but its `AsSqlType` is a `Vec` of the enum
+                typed_parameters.push(quote_spanned! {span=>
+                    ::sqlm_postgres::internal::assert_type_enum_value::<_, Vec<#enum_struct>>(#param)
+                });
+            } else if is_array {
+                typed_parameters.push(quote_spanned! {span=>
                     ::sqlm_postgres::internal::assert_type_enum_slice::<_, #enum_struct>(&#param)
                 });
             } else {
-                typed_parameters.push(quote! {
+                typed_parameters.push(quote_spanned! {span=>
                     ::sqlm_postgres::internal::assert_type_enum::<_, #enum_struct>(&#param)
                 });
             }
@@ -240,19 +515,65 @@ pub fn sql(item: TokenStream) -> TokenStream {
             .into();
         };
 
-        typed_parameters.push(quote! {
-            ::sqlm_postgres::internal::assert_type::<_, _, #ty_borrowed, #ty_owned>(&(#param))
-        });
+        // Point type errors at the argument instead of the whole macro invocation.
+        let ty_owned = diagnostic::respan(ty_owned, span);
+        let ty_borrowed = diagnostic::respan(ty_borrowed, span);
+        let param = if unnest_parameters.contains(&(typed_parameters.len() + 1)) {
+            quote_spanned! {span=>
+                ::sqlm_postgres::internal::assert_type_value::<_, _, #ty_borrowed, #ty_owned>(#param)
+            }
+        } else {
+            quote_spanned! {span=>
+                ::sqlm_postgres::internal::assert_type::<_, _, #ty_borrowed, #ty_owned>(&(#param))
+            }
+        };
+        if is_domain(ty) {
+            // Written as its base type, see `Domain`.
+            typed_parameters.push(quote! { ::sqlm_postgres::internal::Domain(#param) });
+        } else {
+            typed_parameters.push(param);
+        }
     }
 
+    // Parameters marked as secret (e.g. `{password:secret}`) are redacted in traces. The
+    // parameter might be used in multiple placeholders, so it is enough for one of them to be
+    // marked.
+    for index in secret_parameters {
+        let param = &mut typed_parameters[index - 1];
+        *param = quote! { ::sqlm_postgres::internal::Secret(#param) };
+    }
+    let parameter_types = stmt.params().iter().map(|ty| ty.name()).collect::<Vec<_>>();
+
+    let parameters = if arguments.is_empty() {
+        quote! { &[#(&(#typed_parameters),)*] }
+    } else {
+        // Temporaries created in a match arm are dropped at the end of the arm, so all arguments
+        // are borrowed in the scrutinee, and the arm only returns the (typed) parameters by value.
+        // Their references are collected afterwards. Unlike the array above, this isn't subject to
+        // temporary lifetime extension, so the `Sql` has to be used in the same statement.
+        let (bindings, arguments): (Vec<_>, Vec<_>) = arguments.into_iter().unzip();
+        let indices = (0..typed_parameters.len()).map(syn::Index::from);
+        quote! {
+            &::sqlm_postgres::internal::parameters(
+                &match (#(#arguments,)*) {
+                    (#(#bindings,)*) => (#(#typed_parameters,)*),
+                },
+                |parameters| [#(&parameters.#indices,)*],
+            )
+        }
+    };
+
     let col_count = stmt.columns().len();
     if col_count == 0 {
         return quote! {
             ::sqlm_postgres::Sql::<'_, (), ()> {
                 query: #result,
-                parameters: &[#(&(#typed_parameters),)*],
+                parameters: #parameters,
+                parameter_types: &[#(#parameter_types,)*],
                 transaction: None,
                 connection: None,
+                first: false,
+                location: ::std::panic::Location::caller(),
                 marker: ::std::marker::PhantomData,
             }
         }
@@ -260,24 +581,21 @@ pub fn sql(item: TokenStream) -> TokenStream {
     } else if col_count == 1 {
         // Consider the result to be a literal
         let ty = stmt.columns()[0].type_();
-        if let Some((is_array, variants)) = enum_type(ty) {
-            let mut enum_variants: Vec<Type> = Vec::with_capacity(variants.len());
-            for variant in variants {
-                let name = const_name(&variant);
-                enum_variants.push(parse_quote!(::sqlm_postgres::types::EnumVariant<#name>));
-            }
-
+        if let Some((is_array, enum_struct)) = enum_type(ty) {
             let enum_struct = if is_array {
-                quote! { ::sqlm_postgres::types::Array<Vec<::sqlm_postgres::types::Enum<(#(#enum_variants,)*)>>> }
+                quote! { ::sqlm_postgres::types::Array<Vec<#enum_struct>> }
             } else {
-                quote! { ::sqlm_postgres::types::Primitive<::sqlm_postgres::types::Enum<(#(#enum_variants,)*)>> }
+                quote! { ::sqlm_postgres::types::Primitive<#enum_struct> }
             };
             return quote! {
                 ::sqlm_postgres::Sql::<'_, #enum_struct, _> {
                     query: #result,
-                    parameters: &[#(&(#typed_parameters),)*],
+                    parameters: #parameters,
+                    parameter_types: &[#(#parameter_types,)*],
                     transaction: None,
                     connection: None,
+                    first: false,
+                    location: ::std::panic::Location::caller(),
                     marker: ::std::marker::PhantomData,
                 }
             }
@@ -287,9 +605,12 @@ pub fn sql(item: TokenStream) -> TokenStream {
                 return quote! {
                     ::sqlm_postgres::Sql::<'_, ::sqlm_postgres::types::Array<#ty>, _> {
                         query: #result,
-                        parameters: &[#(&(#typed_parameters),)*],
+                        parameters: #parameters,
+                        parameter_types: &[#(#parameter_types,)*],
                         transaction: None,
                         connection: None,
+                        first: false,
+                        location: ::std::panic::Location::caller(),
                         marker: ::std::marker::PhantomData,
                     }
                 }
@@ -298,9 +619,12 @@ pub fn sql(item: TokenStream) -> TokenStream {
                 return quote! {
                     ::sqlm_postgres::Sql::<'_, ::sqlm_postgres::types::Primitive<#ty>, _> {
                         query: #result,
-                        parameters: &[#(&(#typed_parameters),)*],
+                        parameters: #parameters,
+                        parameter_types: &[#(#parameter_types,)*],
                         transaction: None,
                         connection: None,
+                        first: false,
+                        location: ::std::panic::Location::caller(),
                         marker: ::std::marker::PhantomData,
                     }
                 }
@@ -316,27 +640,17 @@ pub fn sql(item: TokenStream) -> TokenStream {
         }
     }
 
-    let mut columns = stmt.columns().iter().collect::<Vec<_>>();
-    columns.sort_by_key(|c| c.name());
-
-    let mut struct_columns: Vec<Type> = Vec::with_capacity(columns.len());
-    for column in columns {
+    let mut columns = Vec::with_capacity(stmt.columns().len());
+    for column in stmt.columns() {
         let ty = column.type_();
-        let name = const_name(column.name());
-        if let Some((is_array, variants)) = enum_type(ty) {
-            let mut enum_variants: Vec<Type> = Vec::with_capacity(variants.len());
-            for variant in variants {
-                let name = const_name(&variant);
-                enum_variants.push(parse_quote!(::sqlm_postgres::types::EnumVariant<#name>));
-            }
-
+        let rust_ty = if let Some((is_array, enum_struct)) = enum_type(ty) {
             if is_array {
-                struct_columns.push(parse_quote!(::sqlm_postgres::types::StructColumn<Vec<::sqlm_postgres::types::Enum<(#(#enum_variants,)*)>>, #name>));
+                quote!(Vec<#enum_struct>)
             } else {
-                struct_columns.push(parse_quote!(::sqlm_postgres::types::StructColumn<::sqlm_postgres::types::Enum<(#(#enum_variants,)*)>, #name>));
+                enum_struct
             }
         } else if let Some((ty, _, _)) = postgres_to_rust_type(ty) {
-            struct_columns.push(parse_quote!(::sqlm_postgres::types::StructColumn<#ty, #name>));
+            ty
         } else {
             return syn::Error::new(
                 input.query.span(),
@@ -345,15 +659,37 @@ pub fn sql(item: TokenStream) -> TokenStream {
             .into_compile_error()
             .into();
         };
+        columns.push((column.name(), rust_ty));
     }
 
-    let type_struct = quote! { ::sqlm_postgres::types::Struct<(#(#struct_columns,)*)> };
+    // Two columns can also be collected into a map, which needs to know which one is the key.
+    let pair = if let [(_, key), (_, value)] = columns.as_slice() {
+        Some((key.clone(), value.clone()))
+    } else {
+        None
+    };
+
+    columns.sort_by_key(|(name, _)| *name);
+    let struct_columns = columns.into_iter().map(|(name, ty)| {
+        let name = const_name(name);
+        quote! { ::sqlm_postgres::types::StructColumn<#ty, #name> }
+    });
+
+    let type_struct = match pair {
+        Some((key, value)) => {
+            quote! { ::sqlm_postgres::types::Pair<#key, #value, (#(#struct_columns,)*)> }
+        }
+        None => quote! { ::sqlm_postgres::types::Struct<(#(#struct_columns,)*)> },
+    };
     quote! {
         ::sqlm_postgres::Sql::<'_, #type_struct, _> {
             query: #result,
-            parameters: &[#(&(#typed_parameters),)*],
+            parameters: #parameters,
+            parameter_types: &[#(#parameter_types,)*],
             transaction: None,
             connection: None,
+            first: false,
+            location: ::std::panic::Location::caller(),
             marker: ::std::marker::PhantomData,
         }
     }
@@ -374,6 +710,31 @@ fn postgres_to_rust_type(
         return postgres_to_rust_type(ty).map(|(ty, _, _)| (quote!(Vec<#ty>), quote!([#ty]), true));
     }
 
+    // e.g. `CREATE DOMAIN email AS TEXT`
+    if let Kind::Domain(ty) = ty.kind() {
+        return postgres_to_rust_type(ty);
+    }
+
+    if let Kind::Range(ty) = ty.kind() {
+        // `tsrange` elements are the only place `timestamp` (without time zone) is supported
+        #[cfg(feature = "time")]
+        if <::time::PrimitiveDateTime as FromSql>::accepts(ty) {
+            return Some((
+                quote!(::sqlm_postgres::types::Range<::time::PrimitiveDateTime>),
+                quote!(::sqlm_postgres::types::Range<::time::PrimitiveDateTime>),
+                false,
+            ));
+        }
+
+        return postgres_to_rust_type(ty).map(|(ty, _, _)| {
+            (
+                quote!(::sqlm_postgres::types::Range<#ty>),
+                quote!(::sqlm_postgres::types::Range<#ty>),
+                false,
+            )
+        });
+    }
+
     match ty {
         ty if <String as FromSql>::accepts(ty) => Some((quote!(String), quote!(str), false)),
         ty if <i64 as FromSql>::accepts(ty) => Some((quote!(i64), quote!(i64), false)),
@@ -386,6 +747,18 @@ fn postgres_to_rust_type(
             quote!(::sqlm_postgres::types::Bytea),
             false,
         )),
+        ty if <std::net::IpAddr as FromSql>::accepts(ty) => Some((
+            quote!(::std::net::IpAddr),
+            quote!(::std::net::IpAddr),
+            false,
+        )),
+
+        // hstore (citext and ltree are already accepted as strings)
+        ty if <HashMap<String, Option<String>> as FromSql>::accepts(ty) => Some((
+            quote!(::std::collections::HashMap<String, Option<String>>),
+            quote!(::std::collections::HashMap<String, Option<String>>),
+            false,
+        )),
 
         // serde_json::Value
         #[cfg(feature = "json")]
@@ -423,41 +796,252 @@ fn postgres_to_rust_type(
             false,
         )),
 
+        // cidr::IpCidr
+        #[cfg(feature = "cidr")]
+        ty if <::cidr::IpCidr as FromSql>::accepts(ty) => {
+            Some((quote!(::cidr::IpCidr), quote!(::cidr::IpCidr), false))
+        }
+
+        // eui48::MacAddress
+        #[cfg(feature = "eui48")]
+        ty if <::eui48::MacAddress as FromSql>::accepts(ty) => Some((
+            quote!(::eui48::MacAddress),
+            quote!(::eui48::MacAddress),
+            false,
+        )),
+
+        // rust_decimal::Decimal
+        #[cfg(feature = "decimal")]
+        ty if <::rust_decimal::Decimal as FromSql>::accepts(ty) => Some((
+            quote!(::rust_decimal::Decimal),
+            quote!(::rust_decimal::Decimal),
+            false,
+        )),
+
         // Unsupported
         _ => None,
     }
 }
 
-fn enum_type(ty: &postgres::types::Type) -> Option<(bool, Vec<String>)> {
+/// Whether the type is a domain or an array of a domain.
+fn is_domain(ty: &postgres::types::Type) -> bool {
     use postgres::types::Kind;
-    let mut data = match ty.kind() {
-        Kind::Enum(variants) => Some((false, variants.clone())),
-        Kind::Array(ty) => {
-            if let Kind::Enum(variants) = ty.kind() {
-                Some((true, variants.clone()))
-            } else {
-                None
-            }
-        }
-        _ => None,
-    };
-    if let Some((_, variants)) = &mut data {
-        variants.sort();
+    match ty.kind() {
+        Kind::Domain(_) => true,
+        Kind::Array(ty) => matches!(ty.kind(), Kind::Domain(_)),
+        _ => false,
     }
-    data
+}
+
+/// The `Enum` marker type of an enum (or enum array) type, identified by its schema-qualified
+/// name and its sorted variants.
+fn enum_type(ty: &postgres::types::Type) -> Option<(bool, proc_macro2::TokenStream)> {
+    use postgres::types::Kind;
+    let (is_array, ty) = match ty.kind() {
+        Kind::Enum(_) => (false, ty),
+        Kind::Array(inner) if matches!(inner.kind(), Kind::Enum(_)) => (true, inner),
+        _ => return None,
+    };
+    let Kind::Enum(variants) = ty.kind() else {
+        return None;
+    };
+
+    let mut variants = variants.clone();
+    variants.sort();
+    let variants = variants.iter().map(|variant| {
+        let name = const_name(variant);
+        quote! { ::sqlm_postgres::types::EnumVariant<#name> }
+    });
+    let qualified_name = format!("{}.{}", ty.schema(), ty.name());
+    let name = const_name(&qualified_name);
+    Some((
+        is_array,
+        quote! { ::sqlm_postgres::types::Enum<#name, (#(#variants,)*)> },
+    ))
 }
 
 struct Parameter {
     expr: Box<Expr>,
     index: Option<usize>,
+    /// Whether the fields of the argument are used, e.g. `{user:insert(User)}`.
+    used: bool,
+}
+
+/// A part of the rewritten query, see [`query_error`].
+struct Segment {
+    /// The byte offset of the segment in the rewritten query.
+    offset: usize,
+    /// The byte range of the segment in the original query.
+    range: Range<usize>,
+    /// Whether the segment is copied verbatim from the original query (as opposed to a
+    /// placeholder or an escaped curly brace).
+    text: bool,
+}
+
+/// Turn an error returned when preparing the rewritten `query` into a compile error pointing at
+/// the position reported by Postgres (mapped back to the original query).
+fn query_error(
+    lit: &LitStr,
+    query: &str,
+    segments: &[Segment],
+    err: postgres::Error,
+) -> proc_macro2::TokenStream {
+    let Some(db_error) = err.as_db_error() else {
+        return syn::Error::new(lit.span(), format!("query failed: {err}")).into_compile_error();
+    };
+
+    let mut message = format!("query failed: {}", db_error.message());
+    if let Some(hint) = db_error.hint() {
+        write!(message, "\nhint: {hint}").unwrap();
+    }
+
+    // The position is a 1-based char index into the rewritten query.
+    let offset = match db_error.position() {
+        Some(ErrorPosition::Original(position)) => query
+            .char_indices()
+            .nth((*position as usize).saturating_sub(1))
+            .map(|(offset, _)| offset),
+        _ => None,
+    };
+    let Some(offset) = offset else {
+        return syn::Error::new(lit.span(), message).into_compile_error();
+    };
+
+    let Some(segment) = segments.iter().rev().find(|s| s.offset <= offset) else {
+        return syn::Error::new(lit.span(), message).into_compile_error();
+    };
+    let range = if segment.text {
+        // Point at the word starting at the reported position.
+        let start = segment.range.start + (offset - segment.offset);
+        let value = lit.value();
+        let word = &value[start..segment.range.end];
+        let len = match word
+            .char_indices()
+            .find(|(_, c)| !c.is_alphanumeric() && *c != '_')
+        {
+            // Underline at least the (possibly multi-byte) char at the position.
+            Some((0, c)) => c.len_utf8(),
+            Some((i, _)) => i,
+            None => word.len(),
+        };
+        start..start + len
+    } else {
+        segment.range.clone()
+    };
+
+    diagnostic::error(lit, range, &message).into_compile_error()
+}
+
+/// Parse the expression of a placeholder like `{user.id}`, with its tokens pointing at the
+/// placeholder. If the expression starts with a named argument (e.g. `user = u`), the argument's
+/// expression is used instead of a variable of that name.
+fn placeholder_expr(
+    lit: &LitStr,
+    range: Range<usize>,
+    expr: &str,
+    named_arguments: &mut HashMap<String, Parameter>,
+) -> Result<(proc_macro2::TokenStream, proc_macro2::Span), syn::Error> {
+    let span = diagnostic::subspan(lit, range.clone()).unwrap_or_else(|| lit.span());
+    let Ok(mut expr) = syn::parse_str(expr)
+        .and_then(|tokens| syn::parse2::<Expr>(diagnostic::respan(tokens, span)))
+    else {
+        return Err(diagnostic::error(
+            lit,
+            range,
+            &format!("invalid expression `{expr}`"),
+        ));
+    };
+
+    let root = expr_root(&mut expr);
+    if let Expr::Path(path) = root
+        && path.qself.is_none()
+        && let Some(ident) = path.path.get_ident()
+        && let Some(param) = named_arguments.get_mut(&ident.to_string())
+    {
+        param.used = true;
+        let arg = &param.expr;
+        *root = syn::parse_quote_spanned! {span=> (#arg) };
+    }
+
+    Ok((expr.into_token_stream(), span))
+}
+
+/// The expression a placeholder expression starts with (e.g. `user` for `user.name.trim()`).
+fn expr_root(expr: &mut Expr) -> &mut Expr {
+    match expr {
+        Expr::Field(syn::ExprField { base, .. })
+        | Expr::Index(syn::ExprIndex { expr: base, .. })
+        | Expr::MethodCall(syn::ExprMethodCall { receiver: base, .. }) => expr_root(base),
+        expr => expr,
+    }
+}
+
+/// The path of the hidden macro generated for the struct at `path` (e.g. `models::User` becomes
+/// `models::__sqlm_columns_User`), which is defined next to the struct.
+fn struct_macro_path(path: &str, prefix: &str) -> String {
+    match path.rsplit_once("::") {
+        Some((module, name)) => format!("{module}::{prefix}{}", name.trim_start_matches("r#")),
+        None => format!("{prefix}{}", path.trim_start_matches("r#")),
+    }
+}
+
+/// Invoke the macro generated by the `FromRow` (or `ToRow`) derive at `macro_path`, which calls
+/// `sql!` again with the struct's columns prepended (as `@columns "macro_path" ["col", ...]`).
+fn expand_columns(
+    lit: &LitStr,
+    range: Range<usize>,
+    macro_path: &str,
+    input: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let span = diagnostic::subspan(lit, range.clone()).unwrap_or_else(|| lit.span());
+    let Ok(path) = syn::parse_str::<syn::Path>(macro_path) else {
+        return diagnostic::error(lit, range, "invalid struct path").into_compile_error();
+    };
+    let path = diagnostic::respan(path.into_token_stream(), span);
+    let key = LitStr::new(macro_path, span);
+    quote! { #path! { #key; #input } }
+}
+
+/// The identifier of a struct field named `name`, which is a raw identifier for keywords (e.g.
+/// `r#type` for the column `type`).
+fn field_ident(name: &str, span: proc_macro2::Span) -> proc_macro2::Ident {
+    match syn::parse_str::<proc_macro2::Ident>(name) {
+        Ok(_) => proc_macro2::Ident::new(name, span),
+        Err(_) => proc_macro2::Ident::new_raw(name, span),
+    }
 }
 
 #[derive(Debug)]
 struct Input {
+    /// The columns of structs used in the query (e.g. `{User::*}` or `{user:insert(User)}`), see
+    /// [`expand_columns`].
+    columns: Vec<(LitStr, Vec<Column>)>,
     query: LitStr,
     arguments: Option<Arguments>,
 }
 
+#[derive(Debug)]
+struct Column {
+    name: LitStr,
+    /// Whether the field is an `Option` (only provided by `ToRow`).
+    optional: bool,
+    /// The Postgres type, if known (only provided by `ToRow`).
+    ty: Option<LitStr>,
+}
+
+impl Parse for Column {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        let optional = input.parse::<Option<syn::Token![?]>>()?.is_some();
+        let ty = if input.parse::<Option<syn::Token![:]>>()?.is_some() {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Column { name, optional, ty })
+    }
+}
+
 #[derive(Debug)]
 struct Arguments {
     #[allow(unused)]
@@ -467,7 +1051,22 @@ struct Arguments {
 
 impl Parse for Input {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut columns = Vec::new();
+        while input.peek(syn::Token![@]) {
+            input.parse::<syn::Token![@]>()?;
+            let keyword: syn::Ident = input.parse()?;
+            if keyword != "columns" {
+                return Err(syn::Error::new(keyword.span(), "expected `columns`"));
+            }
+            let key: LitStr = input.parse()?;
+            let content;
+            syn::bracketed!(content in input);
+            let names = Punctuated::<Column, Comma>::parse_terminated(&content)?;
+            columns.push((key, names.into_iter().collect()));
+        }
+
         Ok(Input {
+            columns,
             query: input.parse()?,
             arguments: input
                 .peek(Comma)
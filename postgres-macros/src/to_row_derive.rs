@@ -0,0 +1,144 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, Data, DataStruct, DeriveInput, Error, Fields, PathArguments, Type, token};
+
+use crate::from_row_derive::{
+    FIELDS_MACRO_PREFIX, Kind, MacroColumn, columns_macro, extract_inner_type,
+};
+use crate::options::OptionExpr;
+
+pub fn expand_derive_to_row(input: DeriveInput) -> syn::Result<TokenStream> {
+    let DeriveInput {
+        attrs: _,
+        vis: _,
+        ident,
+        generics,
+        data,
+    } = input;
+
+    let Data::Struct(DataStruct {
+        fields: Fields::Named(fields),
+        ..
+    }) = data
+    else {
+        return Err(Error::new(
+            ident.span(),
+            "ToRow can only be derived from named structs",
+        ));
+    };
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    // in declaration order, for `{value:insert(Struct)}`, `{value:set(Struct)}` and
+    // `{rows:unnest(Struct)}` in queries
+    let mut columns = Vec::with_capacity(fields.named.len());
+    for f in &fields.named {
+        let opts = extract_field_options(&f.attrs)?;
+        let (inner_ty, kind) = extract_inner_type(&f.ty)?;
+        let pg_type = opts
+            .pg_type
+            .or_else(|| postgres_type(inner_ty).map(String::from));
+        columns.push(MacroColumn {
+            name: f.ident.as_ref().unwrap().unraw().to_string(),
+            optional: matches!(kind, Kind::Option),
+            pg_type,
+        });
+    }
+    let columns_macro = columns_macro(&format_ident!("{}{}", FIELDS_MACRO_PREFIX, ident), &columns);
+
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::sqlm_postgres::ToRow for #ident #ty_generics #where_clause {}
+
+        #columns_macro
+    })
+}
+
+/// The Postgres type of a field, used to cast the arrays of `{rows:unnest(Struct)}` (as Postgres
+/// cannot infer them). Only needs to be right enough for the query to be prepared, as the
+/// parameters are type checked against the result like any other.
+///
+/// As the derive cannot resolve types, only primitives and fully qualified paths of known types
+/// (e.g. `time::OffsetDateTime`, not just `OffsetDateTime`) are considered. Everything else needs
+/// `#[sqlm(pg_type = "...")]`.
+fn postgres_type(ty: &Type) -> Option<&'static str> {
+    if let Type::Reference(r) = ty {
+        return postgres_type(&r.elem);
+    }
+    let Type::Path(p) = ty else {
+        return None;
+    };
+    if p.qself.is_some() {
+        return None;
+    }
+    let path = p
+        .path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::");
+    let ty = match path.as_str() {
+        "i16" => "int2",
+        "i32" => "int4",
+        "i64" => "int8",
+        "f32" => "float4",
+        "f64" => "float8",
+        "bool" => "bool",
+        "str" | "String" | "std::string::String" | "alloc::string::String" => "text",
+        "Vec" | "std::vec::Vec" | "alloc::vec::Vec" => match &p.path.segments.last()?.arguments {
+            PathArguments::AngleBracketed(args)
+                if matches!(
+                    args.args.first(),
+                    Some(syn::GenericArgument::Type(Type::Path(p))) if p.path.is_ident("u8")
+                ) =>
+            {
+                "bytea"
+            }
+            _ => return None,
+        },
+        "sqlm_postgres::types::Bytea" => "bytea",
+        "std::net::IpAddr" | "core::net::IpAddr" => "inet",
+        "serde_json::Value" | "sqlm_postgres::types::Json" => "jsonb",
+        "time::Date" => "date",
+        "time::OffsetDateTime" => "timestamptz",
+        "uuid::Uuid" => "uuid",
+        "pgvector::Vector" => "vector",
+        "cidr::IpCidr" => "cidr",
+        "eui48::MacAddress" => "macaddr",
+        "rust_decimal::Decimal" => "numeric",
+        _ => return None,
+    };
+    Some(ty)
+}
+
+#[derive(Default)]
+struct FieldOptions {
+    pg_type: Option<String>,
+}
+
+fn extract_field_options(attrs: &[Attribute]) -> Result<FieldOptions, Error> {
+    let mut opts = FieldOptions::default();
+
+    for attr in attrs {
+        if !attr.path().is_ident("sqlm") {
+            continue;
+        }
+
+        for opt in attr.parse_args_with(Punctuated::<OptionExpr, token::Comma>::parse_terminated)? {
+            if opt.key.is_ident("pg_type") {
+                let Some(value) = opt.value else {
+                    return Err(Error::new_spanned(opt.key, "pg_type must have a value"));
+                };
+
+                opts.pg_type = Some(value.value());
+            } else {
+                return Err(Error::new_spanned(opt.key, "unknown option"));
+            }
+        }
+    }
+
+    Ok(opts)
+}
@@ -0,0 +1,6 @@
+use sqlm_postgres::{sql, Sql};
+
+fn main() {
+    let id = "1".to_string();
+    let _: Sql<'_, _, ()> = sql!("SELECT {id::int8} IS NULL");
+}
@@ -0,0 +1,11 @@
+use sqlm_postgres_macros::sql;
+
+#[allow(dead_code)]
+struct User {
+    id: i64,
+}
+
+#[tokio::main]
+async fn main() {
+    let _: Vec<i64> = sql!("SELECT {User::*} FROM users").await.unwrap();
+}
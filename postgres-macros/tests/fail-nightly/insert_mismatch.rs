@@ -0,0 +1,19 @@
+use sqlm_postgres::{sql, ToRow};
+
+#[derive(ToRow)]
+struct NewUser {
+    name: String,
+}
+
+#[derive(ToRow)]
+struct NewAccount {
+    email: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let account = NewAccount {
+        email: "new@example.com".to_string(),
+    };
+    sql!("INSERT INTO users {account:insert(NewUser)}").await.unwrap();
+}
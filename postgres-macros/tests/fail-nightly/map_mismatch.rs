@@ -0,0 +1,8 @@
+use std::collections::HashMap;
+
+use sqlm_postgres::sql;
+
+#[tokio::main]
+async fn main() {
+    let _: HashMap<String, i64> = sql!("SELECT id, name FROM users").await.unwrap();
+}
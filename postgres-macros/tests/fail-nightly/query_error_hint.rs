@@ -0,0 +1,9 @@
+use sqlm_postgres::{sql, Sql};
+
+fn main() {
+    let _: Sql<'_, _, i64> = sql!(
+        "SELECT id \
+         FROM users \
+         WHERE id + 'a'::TEXT = 1"
+    );
+}
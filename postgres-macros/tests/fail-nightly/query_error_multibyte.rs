@@ -0,0 +1,5 @@
+use sqlm_postgres::{sql, Sql};
+
+fn main() {
+    let _: Sql<'_, _, String> = sql!("SELECT ¿name FROM users");
+}
@@ -0,0 +1,28 @@
+use sqlm_postgres::{sql, Enum, FromSql, ToRow, ToSql};
+
+#[derive(Debug, Default, FromSql, ToSql, Enum)]
+#[postgres(name = "role")]
+enum Role {
+    #[default]
+    #[postgres(name = "user")]
+    User,
+    #[postgres(name = "admin")]
+    Admin,
+}
+
+#[derive(ToRow)]
+struct NewUser {
+    name: String,
+    role: Role,
+}
+
+#[tokio::main]
+async fn main() {
+    let users = vec![NewUser {
+        name: "new".to_string(),
+        role: Role::User,
+    }];
+    sql!("INSERT INTO users (name, role) {users:unnest(NewUser)}")
+        .await
+        .unwrap();
+}
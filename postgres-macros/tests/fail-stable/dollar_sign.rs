@@ -0,0 +1,3 @@
+fn main() {
+    sqlm_postgres_macros::sql!("SELECT {id} = $1", id = 1);
+}
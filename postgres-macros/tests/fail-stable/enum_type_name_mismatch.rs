@@ -0,0 +1,17 @@
+use postgres_types::{FromSql, ToSql};
+use sqlm_postgres_macros::{sql, Enum};
+
+#[derive(Debug, FromSql, ToSql, Enum, PartialEq, Eq)]
+#[postgres(name = "order_status")]
+enum OrderStatus {
+    #[postgres(name = "active")]
+    Active,
+    #[postgres(name = "inactive")]
+    Inactive,
+}
+
+#[tokio::main]
+async fn main() {
+    let status = OrderStatus::Active;
+    let _: bool = sql!("SELECT {status} = 'active'::payment_status").await.unwrap();
+}
@@ -0,0 +1,15 @@
+use sqlm_postgres::sql;
+
+struct User {
+    id: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let user = User {
+        id: "1".to_string(),
+    };
+    let _: String = sql!("SELECT name FROM users WHERE id = {user.id}")
+        .await
+        .unwrap();
+}
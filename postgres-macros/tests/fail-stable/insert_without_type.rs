@@ -0,0 +1,14 @@
+use sqlm_postgres::{sql, ToRow};
+
+#[derive(ToRow)]
+struct NewUser {
+    name: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let user = NewUser {
+        name: "new".to_string(),
+    };
+    sql!("INSERT INTO users {user:insert}").await.unwrap();
+}
@@ -0,0 +1,3 @@
+fn main() {
+    sqlm_postgres_macros::sql!("foobar {}");
+}
@@ -0,0 +1,6 @@
+use sqlm_postgres::{sql, Sql};
+
+fn main() {
+    let name = "first";
+    let _: Sql<'_, _, String> = sql!("SELECT name FROM users WHERE id = {}", name);
+}
@@ -0,0 +1,6 @@
+use sqlm_postgres::{sql, Sql};
+
+fn main() {
+    let id = 1i64;
+    let _: Sql<'_, _, String> = sql!("SELECT nme FROM users WHERE id = {id}");
+}
@@ -0,0 +1,12 @@
+use sqlm_postgres_macros::{sql, FromRow};
+
+#[derive(Debug, FromRow)]
+struct User {
+    id: i32,
+    name: String,
+}
+
+#[tokio::main]
+async fn main() {
+    let _: User = sql!("SELECT id, name FROM users WHERE id = 1").await.unwrap();
+}
@@ -0,0 +1,21 @@
+use std::net::IpAddr;
+
+use sqlm_postgres::{sql, ToRow};
+
+#[derive(ToRow)]
+struct NewUser {
+    name: String,
+    // only `std::net::IpAddr` is known, as the derive cannot resolve imports
+    address: IpAddr,
+}
+
+#[tokio::main]
+async fn main() {
+    let users = vec![NewUser {
+        name: "new".to_string(),
+        address: [127, 0, 0, 1].into(),
+    }];
+    sql!("INSERT INTO users (name, address) {users:unnest(NewUser)}")
+        .await
+        .unwrap();
+}
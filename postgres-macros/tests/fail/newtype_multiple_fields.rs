@@ -0,0 +1,4 @@
+#[derive(sqlm_postgres::Newtype)]
+struct Id(i64, i64);
+
+fn main() {}
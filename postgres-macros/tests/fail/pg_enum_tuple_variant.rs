@@ -0,0 +1,8 @@
+#[derive(sqlm_postgres::PgEnum)]
+#[sqlm(name = "role")]
+enum Role {
+    User,
+    Admin(i64),
+}
+
+fn main() {}
@@ -56,3 +56,21 @@ fn parameter_reuse_variable() {
 fn fmt_debug(v: impl Debug) -> String {
     format!("{:?}", v)
 }
+
+#[test]
+fn parameter_reuse_secret() {
+    let password = "secret";
+    let sql: Sql<'_, _, ()> = sql!(
+        "SELECT * FROM users \
+         WHERE name = {password:secret} OR name = {password}",
+    );
+    assert_eq!(
+        sql.query,
+        "SELECT * FROM users WHERE name = $1 OR name = $1",
+    );
+    assert_eq!(
+        sql.parameters.iter().map(fmt_debug).collect::<Vec<_>>(),
+        vec!["<redacted>"]
+    );
+    assert_eq!(sql.parameter_types, ["text"]);
+}
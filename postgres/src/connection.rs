@@ -2,18 +2,27 @@
 #![allow(clippy::manual_async_fn)]
 
 use std::future::Future;
+use std::sync::Arc;
 
 use deadpool_postgres::GenericClient;
 use tokio_postgres::Row;
 use tokio_postgres::types::ToSql;
 
-use crate::error::{Error, ErrorKind};
+use crate::error::Error;
+use crate::pool::PoolContext;
+use crate::telemetry::ConnectionInfo;
 
 /// A database transaction.
-pub struct Transaction<'t>(pub(crate) deadpool_postgres::Transaction<'t>);
+pub struct Transaction<'t>(
+    pub(crate) deadpool_postgres::Transaction<'t>,
+    pub(crate) Arc<PoolContext>,
+);
 
 /// An asynchronous PostgreSQL client (basically a non-transactional connection).
-pub struct Session(pub(crate) deadpool_postgres::Client);
+pub struct Session(
+    pub(crate) deadpool_postgres::Client,
+    pub(crate) Arc<PoolContext>,
+);
 
 /// A trait used to allow functions to accept connections without having to explicit about whether
 /// it's a transaction or not.
@@ -46,11 +55,28 @@ pub trait Connection: Send + Sync {
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Vec<Row>, Error>> + Send + 'a;
 
+    /// Returns the first row (if any) and ignores the rest.
+    ///
+    /// The default implementation fetches all rows via [`Connection::query`]; the connections of
+    /// this crate override it to stop reading after the first row.
+    fn query_first<'a>(
+        &'a self,
+        query: &'a str,
+        parameters: &'a [&'a (dyn ToSql + Sync)],
+    ) -> impl Future<Output = Result<Option<Row>, Error>> + Send + 'a {
+        async move { Ok(self.query(query, parameters).await?.into_iter().next()) }
+    }
+
     fn execute<'a>(
         &'a self,
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<(), Error>> + Send + 'a;
+
+    #[doc(hidden)]
+    fn connection_info(&self) -> Option<&ConnectionInfo> {
+        None
+    }
 }
 
 impl Session {
@@ -59,11 +85,8 @@ impl Session {
     }
 
     pub async fn transaction(&mut self) -> Result<Transaction<'_>, Error> {
-        self.0
-            .transaction()
-            .await
-            .map(Transaction)
-            .map_err(Error::from)
+        let tx = self.0.transaction().await?;
+        Ok(Transaction(tx, Arc::clone(&self.1)))
     }
 }
 
@@ -87,25 +110,7 @@ impl Connection for deadpool_postgres::Client {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Row, Error>> + Send + 'a {
-        async move {
-            let stmt = self.prepare_cached(query).await?;
-            match tokio_postgres::Client::query_one(self, &stmt, parameters).await {
-                Ok(result) => Ok(result),
-                Err(err) => {
-                    if let Some(err) = err.as_db_error() {
-                        if err.routine() == Some("RevalidateCachedQuery") {
-                            tracing::warn!(%err, "clearing statement cache");
-                            self.statement_cache.clear();
-                            let stmt = self.prepare_cached(query).await?;
-                            return Ok(
-                                tokio_postgres::Client::query_one(self, &stmt, parameters).await?
-                            );
-                        }
-                    }
-                    Err(err.into())
-                }
-            }
-        }
+        raw::query_one(self, query, parameters)
     }
 
     fn query_opt<'a>(
@@ -113,25 +118,15 @@ impl Connection for deadpool_postgres::Client {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Option<Row>, Error>> + Send + 'a {
-        async move {
-            let stmt = self.prepare_cached(query).await?;
-            match tokio_postgres::Client::query_opt(self, &stmt, parameters).await {
-                Ok(result) => Ok(result),
-                Err(err) => {
-                    if let Some(err) = err.as_db_error() {
-                        if err.routine() == Some("RevalidateCachedQuery") {
-                            tracing::warn!(%err, "clearing statement cache");
-                            self.statement_cache.clear();
-                            let stmt = self.prepare_cached(query).await?;
-                            return Ok(
-                                tokio_postgres::Client::query_opt(self, &stmt, parameters).await?
-                            );
-                        }
-                    }
-                    Err(err.into())
-                }
-            }
-        }
+        raw::query_opt(self, query, parameters)
+    }
+
+    fn query_first<'a>(
+        &'a self,
+        query: &'a str,
+        parameters: &'a [&'a (dyn ToSql + Sync)],
+    ) -> impl Future<Output = Result<Option<Row>, Error>> + Send + 'a {
+        raw::query_first(self, query, parameters)
     }
 
     fn query<'a>(
@@ -139,25 +134,7 @@ impl Connection for deadpool_postgres::Client {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Vec<Row>, Error>> + Send + 'a {
-        async move {
-            let stmt = self.prepare_cached(query).await?;
-            match tokio_postgres::Client::query(self, &stmt, parameters).await {
-                Ok(result) => Ok(result),
-                Err(err) => {
-                    if let Some(err) = err.as_db_error() {
-                        if err.routine() == Some("RevalidateCachedQuery") {
-                            tracing::warn!(%err, "clearing statement cache");
-                            self.statement_cache.clear();
-                            let stmt = self.prepare_cached(query).await?;
-                            return Ok(
-                                tokio_postgres::Client::query(self, &stmt, parameters).await?
-                            );
-                        }
-                    }
-                    Err(err.into())
-                }
-            }
-        }
+        raw::query(self, query, parameters)
     }
 
     fn execute<'a>(
@@ -166,22 +143,8 @@ impl Connection for deadpool_postgres::Client {
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<(), Error>> + Send + 'a {
         async move {
-            let stmt = self.prepare_cached(query).await?;
-            match tokio_postgres::Client::execute(self, &stmt, parameters).await {
-                Ok(_) => Ok(()),
-                Err(err) => {
-                    if let Some(err) = err.as_db_error() {
-                        if err.routine() == Some("RevalidateCachedQuery") {
-                            tracing::warn!(%err, "clearing statement cache");
-                            self.statement_cache.clear();
-                            let stmt = self.prepare_cached(query).await?;
-                            tokio_postgres::Client::execute(self, &stmt, parameters).await?;
-                            return Ok(());
-                        }
-                    }
-                    Err(err.into())
-                }
-            }
+            raw::execute(self, query, parameters).await?;
+            Ok(())
         }
     }
 }
@@ -192,25 +155,7 @@ impl Connection for deadpool_postgres::Transaction<'_> {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Row, Error>> + Send + 'a {
-        async move {
-            let stmt = self.prepare_cached(query).await?;
-            match tokio_postgres::Transaction::query_opt(self, &stmt, parameters).await {
-                Ok(result) => result.ok_or_else(|| ErrorKind::RowNotFound.into()),
-                Err(err) => {
-                    if let Some(err) = err.as_db_error() {
-                        if err.routine() == Some("RevalidateCachedQuery") {
-                            tracing::warn!(%err, "clearing statement cache");
-                            self.statement_cache.clear();
-                            let stmt = self.prepare_cached(query).await?;
-                            return tokio_postgres::Transaction::query_opt(self, &stmt, parameters)
-                                .await?
-                                .ok_or_else(|| ErrorKind::RowNotFound.into());
-                        }
-                    }
-                    Err(err.into())
-                }
-            }
-        }
+        raw::query_one(self, query, parameters)
     }
 
     fn query_opt<'a>(
@@ -218,26 +163,15 @@ impl Connection for deadpool_postgres::Transaction<'_> {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Option<Row>, Error>> + Send + 'a {
-        async move {
-            let stmt = self.prepare_cached(query).await?;
-            match tokio_postgres::Transaction::query_opt(self, &stmt, parameters).await {
-                Ok(result) => Ok(result),
-                Err(err) => {
-                    if let Some(err) = err.as_db_error() {
-                        if err.routine() == Some("RevalidateCachedQuery") {
-                            tracing::warn!(%err, "clearing statement cache");
-                            self.statement_cache.clear();
-                            let stmt = self.prepare_cached(query).await?;
-                            return Ok(tokio_postgres::Transaction::query_opt(
-                                self, &stmt, parameters,
-                            )
-                            .await?);
-                        }
-                    }
-                    Err(err.into())
-                }
-            }
-        }
+        raw::query_opt(self, query, parameters)
+    }
+
+    fn query_first<'a>(
+        &'a self,
+        query: &'a str,
+        parameters: &'a [&'a (dyn ToSql + Sync)],
+    ) -> impl Future<Output = Result<Option<Row>, Error>> + Send + 'a {
+        raw::query_first(self, query, parameters)
     }
 
     fn query<'a>(
@@ -245,25 +179,7 @@ impl Connection for deadpool_postgres::Transaction<'_> {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Vec<Row>, Error>> + Send + 'a {
-        async move {
-            let stmt = self.prepare_cached(query).await?;
-            match tokio_postgres::Transaction::query(self, &stmt, parameters).await {
-                Ok(result) => Ok(result),
-                Err(err) => {
-                    if let Some(err) = err.as_db_error() {
-                        if err.routine() == Some("RevalidateCachedQuery") {
-                            tracing::warn!(%err, "clearing statement cache");
-                            self.statement_cache.clear();
-                            let stmt = self.prepare_cached(query).await?;
-                            return Ok(
-                                tokio_postgres::Transaction::query(self, &stmt, parameters).await?
-                            );
-                        }
-                    }
-                    Err(err.into())
-                }
-            }
-        }
+        raw::query(self, query, parameters)
     }
 
     fn execute<'a>(
@@ -272,22 +188,8 @@ impl Connection for deadpool_postgres::Transaction<'_> {
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<(), Error>> + Send + 'a {
         async move {
-            let stmt = self.prepare_cached(query).await?;
-            match tokio_postgres::Transaction::execute(self, &stmt, parameters).await {
-                Ok(_) => Ok(()),
-                Err(err) => {
-                    if let Some(err) = err.as_db_error() {
-                        if err.routine() == Some("RevalidateCachedQuery") {
-                            tracing::warn!(%err, "clearing statement cache");
-                            self.statement_cache.clear();
-                            let stmt = self.prepare_cached(query).await?;
-                            tokio_postgres::Transaction::execute(self, &stmt, parameters).await?;
-                            return Ok(());
-                        }
-                    }
-                    Err(err.into())
-                }
-            }
+            raw::execute(self, query, parameters).await?;
+            Ok(())
         }
     }
 }
@@ -298,7 +200,8 @@ impl Connection for Session {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Row, Error>> + Send + 'a {
-        Connection::query_one(&self.0, query, parameters)
+        let future = raw::query_one(&self.0, query, parameters);
+        self.1.hooks.run(query, parameters, future)
     }
 
     fn query_opt<'a>(
@@ -306,7 +209,17 @@ impl Connection for Session {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Option<Row>, Error>> + Send + 'a {
-        Connection::query_opt(&self.0, query, parameters)
+        let future = raw::query_opt(&self.0, query, parameters);
+        self.1.hooks.run(query, parameters, future)
+    }
+
+    fn query_first<'a>(
+        &'a self,
+        query: &'a str,
+        parameters: &'a [&'a (dyn ToSql + Sync)],
+    ) -> impl Future<Output = Result<Option<Row>, Error>> + Send + 'a {
+        let future = raw::query_first(&self.0, query, parameters);
+        self.1.hooks.run(query, parameters, future)
     }
 
     fn query<'a>(
@@ -314,7 +227,8 @@ impl Connection for Session {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Vec<Row>, Error>> + Send + 'a {
-        Connection::query(&self.0, query, parameters)
+        let future = raw::query(&self.0, query, parameters);
+        self.1.hooks.run(query, parameters, future)
     }
 
     fn execute<'a>(
@@ -322,7 +236,15 @@ impl Connection for Session {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<(), Error>> + Send + 'a {
-        Connection::execute(&self.0, query, parameters)
+        async move {
+            let future = raw::execute(&self.0, query, parameters);
+            self.1.hooks.run(query, parameters, future).await?;
+            Ok(())
+        }
+    }
+
+    fn connection_info(&self) -> Option<&ConnectionInfo> {
+        Some(&self.1.info)
     }
 }
 
@@ -332,7 +254,8 @@ impl Connection for Transaction<'_> {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Row, Error>> + Send + 'a {
-        Connection::query_one(&self.0, query, parameters)
+        let future = raw::query_one(&self.0, query, parameters);
+        self.1.hooks.run(query, parameters, future)
     }
 
     fn query_opt<'a>(
@@ -340,7 +263,17 @@ impl Connection for Transaction<'_> {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Option<Row>, Error>> + Send + 'a {
-        Connection::query_opt(&self.0, query, parameters)
+        let future = raw::query_opt(&self.0, query, parameters);
+        self.1.hooks.run(query, parameters, future)
+    }
+
+    fn query_first<'a>(
+        &'a self,
+        query: &'a str,
+        parameters: &'a [&'a (dyn ToSql + Sync)],
+    ) -> impl Future<Output = Result<Option<Row>, Error>> + Send + 'a {
+        let future = raw::query_first(&self.0, query, parameters);
+        self.1.hooks.run(query, parameters, future)
     }
 
     fn query<'a>(
@@ -348,7 +281,8 @@ impl Connection for Transaction<'_> {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<Vec<Row>, Error>> + Send + 'a {
-        Connection::query(&self.0, query, parameters)
+        let future = raw::query(&self.0, query, parameters);
+        self.1.hooks.run(query, parameters, future)
     }
 
     fn execute<'a>(
@@ -356,7 +290,15 @@ impl Connection for Transaction<'_> {
         query: &'a str,
         parameters: &'a [&'a (dyn ToSql + Sync)],
     ) -> impl Future<Output = Result<(), Error>> + Send + 'a {
-        Connection::execute(&self.0, query, parameters)
+        async move {
+            let future = raw::execute(&self.0, query, parameters);
+            self.1.hooks.run(query, parameters, future).await?;
+            Ok(())
+        }
+    }
+
+    fn connection_info(&self) -> Option<&ConnectionInfo> {
+        Some(&self.1.info)
     }
 }
 
@@ -380,6 +322,14 @@ where
         (*self).query_opt(query, parameters)
     }
 
+    fn query_first<'a>(
+        &'a self,
+        query: &'a str,
+        parameters: &'a [&'a (dyn ToSql + Sync)],
+    ) -> impl Future<Output = Result<Option<Row>, Error>> + Send + 'a {
+        (*self).query_first(query, parameters)
+    }
+
     fn query<'a>(
         &'a self,
         query: &'a str,
@@ -395,4 +345,146 @@ where
     ) -> impl Future<Output = Result<(), Error>> + Send + 'a {
         (*self).execute(query, parameters)
     }
+
+    fn connection_info(&self) -> Option<&ConnectionInfo> {
+        (*self).connection_info()
+    }
+}
+
+/// The actual implementation of the queries, shared by clients and transactions.
+mod raw {
+    use std::future::Future;
+    use std::pin::pin;
+
+    use deadpool_postgres::{GenericClient, StatementCache};
+    use futures_util::TryStreamExt;
+    use tokio_postgres::types::ToSql;
+    use tokio_postgres::{Row, RowStream, Statement};
+
+    use crate::error::{Error, ErrorKind};
+    use crate::telemetry;
+
+    pub(super) trait CachedClient: GenericClient {
+        fn statement_cache(&self) -> &StatementCache;
+    }
+
+    impl CachedClient for deadpool_postgres::Client {
+        fn statement_cache(&self) -> &StatementCache {
+            &self.statement_cache
+        }
+    }
+
+    impl CachedClient for deadpool_postgres::Transaction<'_> {
+        fn statement_cache(&self) -> &StatementCache {
+            &self.statement_cache
+        }
+    }
+
+    pub(super) async fn query_one(
+        client: &impl CachedClient,
+        query: &str,
+        parameters: &[&(dyn ToSql + Sync)],
+    ) -> Result<Row, Error> {
+        let stream = query_raw(client, query, parameters).await?;
+        expect_opt(stream)
+            .await?
+            .ok_or_else(|| ErrorKind::RowNotFound.into())
+    }
+
+    pub(super) async fn query_opt(
+        client: &impl CachedClient,
+        query: &str,
+        parameters: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error> {
+        let stream = query_raw(client, query, parameters).await?;
+        expect_opt(stream).await
+    }
+
+    pub(super) async fn query_first(
+        client: &impl CachedClient,
+        query: &str,
+        parameters: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error> {
+        let stream = query_raw(client, query, parameters).await?;
+        let row = pin!(stream).try_next().await?;
+        telemetry::record_rows(usize::from(row.is_some()));
+        Ok(row)
+    }
+
+    pub(super) async fn query(
+        client: &impl CachedClient,
+        query: &str,
+        parameters: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error> {
+        let rows = with_statement(client, query, |stmt| async move {
+            client.query(&stmt, parameters).await
+        })
+        .await?;
+        telemetry::record_rows(rows.len());
+        Ok(rows)
+    }
+
+    pub(super) async fn execute(
+        client: &impl CachedClient,
+        query: &str,
+        parameters: &[&(dyn ToSql + Sync)],
+    ) -> Result<u64, Error> {
+        let n = with_statement(client, query, |stmt| async move {
+            client.execute(&stmt, parameters).await
+        })
+        .await?;
+        telemetry::record_affected_rows(n);
+        Ok(n)
+    }
+
+    async fn query_raw(
+        client: &impl CachedClient,
+        query: &str,
+        parameters: &[&(dyn ToSql + Sync)],
+    ) -> Result<RowStream, Error> {
+        with_statement(client, query, |stmt| async move {
+            client.query_raw(&stmt, parameters.iter().copied()).await
+        })
+        .await
+    }
+
+    /// Prepare the query (or take it from the statement cache) and run `f` with it. If the cached
+    /// statement got invalidated (e.g. due to a schema change), the cache is cleared and `f` is
+    /// retried once with a freshly prepared statement.
+    async fn with_statement<C, F, Fut, T>(client: &C, query: &str, f: F) -> Result<T, Error>
+    where
+        C: CachedClient,
+        F: Fn(Statement) -> Fut,
+        Fut: Future<Output = Result<T, tokio_postgres::Error>>,
+    {
+        let stmt = client.prepare_cached(query).await?;
+        match f(stmt).await {
+            Ok(result) => Ok(result),
+            Err(err) => {
+                if let Some(err) = err.as_db_error()
+                    && err.routine() == Some("RevalidateCachedQuery")
+                {
+                    tracing::warn!(%err, "clearing statement cache");
+                    client.statement_cache().clear();
+                    let stmt = client.prepare_cached(query).await?;
+                    return Ok(f(stmt).await?);
+                }
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Read at most one row from the stream, erroring if there is more than one.
+    async fn expect_opt(stream: RowStream) -> Result<Option<Row>, Error> {
+        let mut stream = pin!(stream);
+        let Some(row) = stream.try_next().await? else {
+            telemetry::record_rows(0);
+            return Ok(None);
+        };
+        if stream.try_next().await?.is_some() {
+            return Err(ErrorKind::TooManyRows.into());
+        }
+        telemetry::record_rows(1);
+        Ok(Some(row))
+    }
 }
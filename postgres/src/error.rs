@@ -1,62 +1,166 @@
 use std::{error, fmt};
 
-use http_error::{HttpError, StatusCode};
-pub use tokio_postgres::error::SqlState;
+pub use tokio_postgres::error::{DbError, SqlState};
+#[cfg(feature = "http-error")]
 use tracing::Span;
 
+#[cfg(any(feature = "http-error", feature = "axum"))]
+pub use self::status::{StatusCode, register_constraint};
+
+#[cfg(any(feature = "http-error", feature = "axum"))]
+mod status;
+
 /// An error communicating with the Postgres server.
 #[derive(Debug)]
 pub struct Error {
     pub(crate) kind: ErrorKind,
+    #[cfg(feature = "http-error")]
     span: Span,
 }
 
+/// The kind of constraint a query violated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstraintViolation {
+    /// A unique (or primary key) constraint violation.
+    Unique,
+    /// A foreign key constraint violation.
+    ForeignKey,
+    /// A check constraint violation.
+    Check,
+    /// A not-null constraint violation.
+    NotNull,
+    /// An exclusion constraint violation.
+    Exclusion,
+}
+
+impl ConstraintViolation {
+    fn from_code(code: &SqlState) -> Option<Self> {
+        Some(match *code {
+            SqlState::UNIQUE_VIOLATION => Self::Unique,
+            SqlState::FOREIGN_KEY_VIOLATION => Self::ForeignKey,
+            SqlState::CHECK_VIOLATION => Self::Check,
+            SqlState::NOT_NULL_VIOLATION => Self::NotNull,
+            SqlState::EXCLUSION_VIOLATION => Self::Exclusion,
+            _ => return None,
+        })
+    }
+}
+
+/// The kind of an [`Error`], see [`Error::kind`].
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum ErrorKind {
+    /// The `DATABASE_URL` env variable is not set.
     MissingDatabaseUrlEnv,
+    /// The query returned no rows, but at least one was expected.
     RowNotFound,
+    /// The query returned more than one row, but at most one was expected.
+    TooManyRows,
+    /// The connection to the Postgres server is closed.
+    ConnectionClosed(tokio_postgres::Error),
+    /// The query got canceled by the server, e.g. due to the `statement_timeout`.
+    Timeout(tokio_postgres::Error),
+    /// Timed out waiting for a connection from the connection pool.
+    PoolTimeout,
+    /// The connection pool is closed.
+    PoolClosed,
+    /// Failed to read the value of a column into its Rust type.
+    Decode {
+        column: String,
+        source: tokio_postgres::Error,
+    },
+    /// Any other error returned by Postgres.
     Postgres(tokio_postgres::Error),
+    /// Failed to build the connection pool.
     Build(deadpool_postgres::BuildError),
+    /// Failed to acquire a connection from the connection pool.
     Pool(deadpool_postgres::PoolError),
+    /// A [`QueryHook`](crate::hook::QueryHook) rejected the query.
+    Hook(Box<dyn error::Error + Send + Sync>),
 }
 
 impl Error {
     fn new(kind: ErrorKind) -> Self {
         Self {
             kind,
+            #[cfg(feature = "http-error")]
             span: Span::current(),
         }
     }
 
+    /// The kind of error, e.g. to decide whether a query should be retried.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use sqlm_postgres::error::ErrorKind;
+    ///
+    /// fn should_retry(err: &sqlm_postgres::Error) -> bool {
+    ///     matches!(
+    ///         err.kind(),
+    ///         ErrorKind::ConnectionClosed(_) | ErrorKind::Timeout(_) | ErrorKind::PoolTimeout
+    ///     )
+    /// }
+    /// ```
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    fn postgres_error(&self) -> Option<&tokio_postgres::Error> {
+        match &self.kind {
+            ErrorKind::ConnectionClosed(err)
+            | ErrorKind::Timeout(err)
+            | ErrorKind::Decode { source: err, .. }
+            | ErrorKind::Postgres(err) => Some(err),
+            _ => None,
+        }
+    }
+
     /// Whether this is a row not found error.
     pub fn is_row_not_found(&self) -> bool {
         matches!(self.kind, ErrorKind::RowNotFound)
     }
 
     pub fn code(&self) -> Option<&SqlState> {
-        if let ErrorKind::Postgres(err) = &self.kind {
-            err.code()
-        } else {
-            None
-        }
+        self.postgres_error().and_then(|err| err.code())
+    }
+
+    /// The error reported by the Postgres server, which exposes details like the violated
+    /// constraint, table, column, detail and hint.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sqlm_postgres::{sql, error::ConstraintViolation};
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let err = sql!("INSERT INTO users (id) VALUES (1)").await.unwrap_err();
+    /// assert_eq!(err.constraint_violation(), Some(ConstraintViolation::Unique));
+    /// assert_eq!(err.db_error().and_then(|e| e.constraint()), Some("users_pkey"));
+    /// # }
+    /// ```
+    pub fn db_error(&self) -> Option<&DbError> {
+        self.postgres_error().and_then(|err| err.as_db_error())
+    }
+
+    /// The kind of constraint that got violated, if this is a constraint violation error.
+    pub fn constraint_violation(&self) -> Option<ConstraintViolation> {
+        self.code().and_then(ConstraintViolation::from_code)
+    }
+
+    /// The name of the violated constraint, if this is a constraint violation error.
+    pub fn constraint(&self) -> Option<&str> {
+        self.db_error().and_then(|err| err.constraint())
     }
 
     /// Whether this is a duplicate key error (unique constraint violation).
     pub fn is_duplicate_key(&self) -> bool {
-        if let ErrorKind::Postgres(err) = &self.kind {
-            err.code() == Some(&tokio_postgres::error::SqlState::UNIQUE_VIOLATION)
-        } else {
-            false
-        }
+        self.code() == Some(&SqlState::UNIQUE_VIOLATION)
     }
 
     /// Whether this is a foreign key error (foreign key constraint violation).
     pub fn is_foreign_key(&self) -> bool {
-        if let ErrorKind::Postgres(err) = &self.kind {
-            err.code() == Some(&tokio_postgres::error::SqlState::FOREIGN_KEY_VIOLATION)
-        } else {
-            false
-        }
+        self.code() == Some(&SqlState::FOREIGN_KEY_VIOLATION)
     }
 }
 
@@ -65,9 +169,16 @@ impl error::Error for Error {
         match &self.kind {
             ErrorKind::MissingDatabaseUrlEnv => None,
             ErrorKind::RowNotFound => None,
+            ErrorKind::TooManyRows => None,
+            ErrorKind::ConnectionClosed(err) => Some(err),
+            ErrorKind::Timeout(err) => Some(err),
+            ErrorKind::PoolTimeout => None,
+            ErrorKind::PoolClosed => None,
+            ErrorKind::Decode { source, .. } => Some(source),
             ErrorKind::Postgres(err) => Some(err),
             ErrorKind::Build(err) => Some(err),
             ErrorKind::Pool(err) => Some(err),
+            ErrorKind::Hook(err) => Some(err.as_ref()),
         }
     }
 }
@@ -77,26 +188,35 @@ impl fmt::Display for Error {
         match &self.kind {
             ErrorKind::MissingDatabaseUrlEnv => f.write_str("env DATABASE_URL not set"),
             ErrorKind::RowNotFound => f.write_str("No rows returned, but at least one expected"),
+            ErrorKind::TooManyRows => {
+                f.write_str("More than one row returned, but at most one expected")
+            }
+            ErrorKind::ConnectionClosed(err) => err.fmt(f),
+            ErrorKind::Timeout(err) => err.fmt(f),
+            ErrorKind::PoolTimeout => {
+                f.write_str("timed out waiting for a postgres connection from the pool")
+            }
+            ErrorKind::PoolClosed => f.write_str("postgres connection pool is closed"),
+            ErrorKind::Decode { column, .. } => {
+                write!(f, "failed to read column `{column}`")
+            }
             ErrorKind::Postgres(err) => err.fmt(f),
             ErrorKind::Build(_) => write!(f, "failed to build postgres connection pool"),
             ErrorKind::Pool(_) => write!(f, "failed to acquire postgres connection from pool"),
+            ErrorKind::Hook(_) => f.write_str("query rejected by hook"),
         }
     }
 }
 
-impl HttpError for Error {
-    fn status_code(&self) -> StatusCode {
-        StatusCode::INTERNAL_SERVER_ERROR
-    }
-
-    fn span(&self) -> Option<&tracing::Span> {
-        Some(&self.span)
-    }
-}
-
 impl From<tokio_postgres::Error> for Error {
     fn from(err: tokio_postgres::Error) -> Self {
-        Self::new(ErrorKind::Postgres(err))
+        if err.is_closed() {
+            Self::new(ErrorKind::ConnectionClosed(err))
+        } else if err.code() == Some(&SqlState::QUERY_CANCELED) {
+            Self::new(ErrorKind::Timeout(err))
+        } else {
+            Self::new(ErrorKind::Postgres(err))
+        }
     }
 }
 
@@ -108,7 +228,11 @@ impl From<deadpool_postgres::BuildError> for Error {
 
 impl From<deadpool_postgres::PoolError> for Error {
     fn from(err: deadpool_postgres::PoolError) -> Self {
-        Self::new(ErrorKind::Pool(err))
+        match err {
+            deadpool_postgres::PoolError::Timeout(_) => Self::new(ErrorKind::PoolTimeout),
+            deadpool_postgres::PoolError::Closed => Self::new(ErrorKind::PoolClosed),
+            err => Self::new(ErrorKind::Pool(err)),
+        }
     }
 }
 
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::RwLock;
+
+pub use http::StatusCode;
+use once_cell::sync::Lazy;
+
+use super::{ConstraintViolation, Error, ErrorKind, SqlState};
+
+static CONSTRAINT_OVERRIDES: Lazy<RwLock<HashMap<String, ConstraintOverride>>> =
+    Lazy::new(Default::default);
+
+#[derive(Clone, Copy)]
+struct ConstraintOverride {
+    status: StatusCode,
+    message: Option<&'static str>,
+}
+
+/// Override the HTTP status code (and optionally the user-facing reason) of errors caused by
+/// violating the constraint with the given name.
+///
+/// Without an override, unique and exclusion violations are mapped to `409 Conflict` and foreign
+/// key, check and not-null violations to `422 Unprocessable Entity`.
+///
+/// # Example
+///
+/// ```
+/// use sqlm_postgres::error::StatusCode;
+///
+/// sqlm_postgres::error::register_constraint(
+///     "users_email_key",
+///     StatusCode::CONFLICT,
+///     Some("email taken"),
+/// );
+/// ```
+pub fn register_constraint(
+    constraint: impl Into<String>,
+    status: StatusCode,
+    message: Option<&'static str>,
+) {
+    CONSTRAINT_OVERRIDES
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .insert(constraint.into(), ConstraintOverride { status, message });
+}
+
+impl Error {
+    fn constraint_override(&self) -> Option<ConstraintOverride> {
+        let constraint = self.constraint()?;
+        CONSTRAINT_OVERRIDES
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(constraint)
+            .copied()
+    }
+
+    fn http_status_code(&self) -> StatusCode {
+        if let Some(mapping) = self.constraint_override() {
+            return mapping.status;
+        }
+
+        if let Some(violation) = self.constraint_violation() {
+            return match violation {
+                ConstraintViolation::Unique | ConstraintViolation::Exclusion => {
+                    StatusCode::CONFLICT
+                }
+                ConstraintViolation::ForeignKey
+                | ConstraintViolation::Check
+                | ConstraintViolation::NotNull => StatusCode::UNPROCESSABLE_ENTITY,
+            };
+        }
+
+        match &self.kind {
+            ErrorKind::RowNotFound => StatusCode::NOT_FOUND,
+            ErrorKind::Postgres(err)
+                if err.code() == Some(&SqlState::INVALID_TEXT_REPRESENTATION) =>
+            {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            ErrorKind::PoolTimeout | ErrorKind::PoolClosed => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn http_reason(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(message) = self.constraint_override().and_then(|o| o.message) {
+            return f.write_str(message);
+        }
+
+        if let Some(reason) = self.http_status_code().canonical_reason() {
+            f.write_str(reason)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "http-error")]
+impl http_error::HttpError for Error {
+    fn status_code(&self) -> StatusCode {
+        self.http_status_code()
+    }
+
+    fn reason(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.http_reason(f)
+    }
+
+    fn span(&self) -> Option<&tracing::Span> {
+        Some(&self.span)
+    }
+}
+
+#[cfg(feature = "axum")]
+impl axum_core::response::IntoResponse for Error {
+    fn into_response(self) -> axum_core::response::Response {
+        struct Reason<'a>(&'a Error);
+
+        impl fmt::Display for Reason<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.http_reason(f)
+            }
+        }
+
+        let status = self.http_status_code();
+        if status.is_server_error() {
+            tracing::error!(err = %self, "sql error");
+        }
+
+        (status, Reason(&self).to_string()).into_response()
+    }
+}
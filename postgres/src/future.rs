@@ -8,6 +8,7 @@ use tracing::Instrument;
 
 use crate::error::ErrorKind;
 use crate::query::Query;
+use crate::telemetry;
 use crate::{Error, Sql};
 
 #[cfg(feature = "global_pool")]
@@ -37,8 +38,7 @@ impl<'a, T> SqlFuture<'a, T> {
         T: Query<Cols> + Send + Sync + 'a,
         Cols: Send + Sync + 'a,
     {
-        let span =
-            tracing::debug_span!("sql query", query = sql.query, parameters = ?sql.parameters);
+        let span = telemetry::query_span(sql.query, sql.parameters, sql.parameter_types);
         let start = Instant::now();
 
         SqlFuture {
@@ -46,18 +46,23 @@ impl<'a, T> SqlFuture<'a, T> {
                 // Note: changes here must be applied to `with_connection` below too!
                 async move {
                     let mut i = 1;
-                    loop {
-                        let conn = super::connect().await?;
-                        match T::query(&sql, &conn).await {
+                    let mut session = None;
+                    let result = loop {
+                        let conn = match super::connect().await {
+                            Ok(conn) => session.insert(conn),
+                            Err(err) => break Err(err),
+                        };
+                        if let Some(info) = super::Connection::connection_info(conn) {
+                            info.record(&tracing::Span::current());
+                        }
+                        match T::query(&sql, &*conn).await {
                             Ok(r) => {
-                                let elapsed = start.elapsed();
-                                tracing::trace!(?elapsed, "sql query finished");
-                                return Ok(r);
+                                break Ok(r);
                             }
                             Err(Error {
-                                kind: ErrorKind::Postgres(err),
+                                kind: ErrorKind::ConnectionClosed(_),
                                 ..
-                            }) if err.is_closed() && i <= 5 => {
+                            }) if i <= 5 => {
                                 // retry pool size + 1 times if connection is closed (might have
                                 // received a closed one from the connection pool)
                                 i += 1;
@@ -65,10 +70,23 @@ impl<'a, T> SqlFuture<'a, T> {
                                 continue;
                             }
                             Err(err) => {
-                                return Err(err);
+                                break Err(err);
                             }
                         }
+                    };
+                    let elapsed = start.elapsed();
+                    telemetry::query_finished(sql.query, elapsed, &result);
+                    if let Some(conn) = &session {
+                        telemetry::check_slow_query(
+                            conn,
+                            sql.query,
+                            sql.parameters,
+                            elapsed,
+                            sql.location,
+                        )
+                        .await;
                     }
+                    result
                 }
                 .instrument(span),
             ),
@@ -84,26 +102,26 @@ impl<'a, T> SqlFuture<'a, T> {
         T: Query<Cols> + Send + Sync + 'a,
         Cols: Send + Sync + 'a,
     {
-        let span =
-            tracing::debug_span!("sql query", query = sql.query, parameters = ?sql.parameters);
+        let span = telemetry::query_span(sql.query, sql.parameters, sql.parameter_types);
+        if let Some(info) = conn.connection_info() {
+            info.record(&span);
+        }
         let start = Instant::now();
 
         SqlFuture {
             future: Box::pin(
-                // Note: changes here must be applied to `bew` above too!
+                // Note: changes here must be applied to `new` above too!
                 async move {
                     let mut i = 1;
-                    loop {
+                    let result = loop {
                         match T::query(&sql, &conn).await {
                             Ok(r) => {
-                                let elapsed = start.elapsed();
-                                tracing::trace!(?elapsed, "sql query finished");
-                                return Ok(r);
+                                break Ok(r);
                             }
                             Err(Error {
-                                kind: ErrorKind::Postgres(err),
+                                kind: ErrorKind::ConnectionClosed(_),
                                 ..
-                            }) if err.is_closed() && i <= 5 => {
+                            }) if i <= 5 => {
                                 // retry pool size + 1 times if connection is closed (might have
                                 // received a closed one from the connection pool)
                                 i += 1;
@@ -111,10 +129,21 @@ impl<'a, T> SqlFuture<'a, T> {
                                 continue;
                             }
                             Err(err) => {
-                                return Err(err);
+                                break Err(err);
                             }
                         }
-                    }
+                    };
+                    let elapsed = start.elapsed();
+                    telemetry::query_finished(sql.query, elapsed, &result);
+                    telemetry::check_slow_query(
+                        &conn,
+                        sql.query,
+                        sql.parameters,
+                        elapsed,
+                        sql.location,
+                    )
+                    .await;
+                    result
                 }
                 .instrument(span),
             ),
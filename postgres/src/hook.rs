@@ -0,0 +1,174 @@
+//! Hooks that are called around every query executed on connections of a [`Pool`].
+//!
+//! Hooks can be used for auditing, custom metrics or fault injection, without having to wrap
+//! every query call site.
+//!
+//! # Example
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use sqlm_postgres::hook::{Outcome, QueryHook, QueryInfo};
+//! use sqlm_postgres::pool::Pool;
+//!
+//! struct Audit;
+//!
+//! impl QueryHook for Audit {
+//!     fn after(&self, info: &QueryInfo<'_>, elapsed: Duration, outcome: Outcome) {
+//!         println!("{} took {elapsed:?} ({outcome:?})", info.query());
+//!     }
+//! }
+//!
+//! # fn main() -> Result<(), sqlm_postgres::Error> {
+//! let pool = Pool::from_env(4)?.with_hook(Audit);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! [`Pool`]: crate::pool::Pool
+
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio_postgres::Row;
+use tokio_postgres::types::ToSql;
+
+use crate::Error;
+
+/// A hook called around every query executed on connections of the [`Pool`] it is registered
+/// on (see [`Pool::with_hook`]).
+///
+/// All callbacks default to doing nothing.
+///
+/// [`Pool`]: crate::pool::Pool
+/// [`Pool::with_hook`]: crate::pool::Pool::with_hook
+pub trait QueryHook: Send + Sync + 'static {
+    /// Called before the query is sent to the server. Returning an error (e.g.
+    /// [`ErrorKind::Hook`](crate::error::ErrorKind::Hook)) aborts the query with that error.
+    fn before(&self, info: &QueryInfo<'_>) -> Result<(), Error> {
+        let _ = info;
+        Ok(())
+    }
+
+    /// Called after the query completed successfully.
+    fn after(&self, info: &QueryInfo<'_>, elapsed: Duration, outcome: Outcome) {
+        let _ = (info, elapsed, outcome);
+    }
+
+    /// Called after the query failed.
+    fn error(&self, info: &QueryInfo<'_>, elapsed: Duration, err: &Error) {
+        let _ = (info, elapsed, err);
+    }
+}
+
+/// The query a [`QueryHook`] is called for.
+pub struct QueryInfo<'a> {
+    query: &'a str,
+    parameters: &'a [&'a (dyn ToSql + Sync)],
+}
+
+impl<'a> QueryInfo<'a> {
+    /// The query (with placeholders like `$1`).
+    pub fn query(&self) -> &'a str {
+        self.query
+    }
+
+    /// The parameters bound to the query's placeholders.
+    pub fn parameters(&self) -> &'a [&'a (dyn ToSql + Sync)] {
+        self.parameters
+    }
+}
+
+impl fmt::Debug for QueryInfo<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueryInfo")
+            .field("query", &self.query)
+            .field("parameters", &self.parameters)
+            .finish()
+    }
+}
+
+/// The outcome of a successful query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Outcome {
+    /// The query returned the given number of rows.
+    Rows(usize),
+    /// The statement affected the given number of rows.
+    Affected(u64),
+}
+
+/// The hooks registered on a pool.
+#[derive(Clone, Default)]
+pub(crate) struct Hooks(Vec<Arc<dyn QueryHook>>);
+
+impl Hooks {
+    pub(crate) fn push(&mut self, hook: impl QueryHook) {
+        self.0.push(Arc::new(hook));
+    }
+
+    /// Run `query` surrounded by all hooks.
+    pub(crate) async fn run<T: IntoOutcome>(
+        &self,
+        query: &str,
+        parameters: &[&(dyn ToSql + Sync)],
+        future: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        if self.0.is_empty() {
+            return future.await;
+        }
+
+        let info = QueryInfo { query, parameters };
+        for hook in &self.0 {
+            hook.before(&info)?;
+        }
+
+        let start = Instant::now();
+        let result = future.await;
+        let elapsed = start.elapsed();
+        match &result {
+            Ok(value) => {
+                let outcome = value.outcome();
+                for hook in &self.0 {
+                    hook.after(&info, elapsed, outcome);
+                }
+            }
+            Err(err) => {
+                for hook in &self.0 {
+                    hook.error(&info, elapsed, err);
+                }
+            }
+        }
+        result
+    }
+}
+
+pub(crate) trait IntoOutcome {
+    fn outcome(&self) -> Outcome;
+}
+
+impl IntoOutcome for Row {
+    fn outcome(&self) -> Outcome {
+        Outcome::Rows(1)
+    }
+}
+
+impl IntoOutcome for Option<Row> {
+    fn outcome(&self) -> Outcome {
+        Outcome::Rows(usize::from(self.is_some()))
+    }
+}
+
+impl IntoOutcome for Vec<Row> {
+    fn outcome(&self) -> Outcome {
+        Outcome::Rows(self.len())
+    }
+}
+
+impl IntoOutcome for u64 {
+    fn outcome(&self) -> Outcome {
+        Outcome::Affected(*self)
+    }
+}
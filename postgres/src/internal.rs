@@ -1,7 +1,12 @@
+use std::borrow::Cow;
 use std::marker::PhantomData;
 
-use crate::SqlType;
-use crate::types::Bytea;
+pub use bytes::BytesMut;
+use tokio_postgres::types::{FromSql, FromSqlOwned, IsNull, Kind, ToSql, Type, to_sql_checked};
+
+use crate::row::try_get;
+use crate::types::{Bytea, Pair};
+use crate::{Error, SqlType, ToRow};
 
 #[inline]
 pub const fn assert_type<T, S, Borrowed: ?Sized, Owned>(t: &T) -> &T
@@ -28,6 +33,36 @@ where
     t
 }
 
+/// Like [`assert_type`], but takes the value itself, for parameters created by the macro (e.g.
+/// [`Unnest`]).
+#[inline]
+pub fn assert_type_value<T, S, Borrowed: ?Sized, Owned>(t: T) -> T
+where
+    T: AsSqlType<SqlType = S>,
+    for<'a> Valid<'a, Borrowed, Owned>: From<S>,
+{
+    t
+}
+
+/// Like [`assert_type_enum`], but takes the value itself.
+#[inline]
+pub fn assert_type_enum_value<T, S>(t: T) -> T
+where
+    T: AsSqlType<SqlType = S>,
+{
+    t
+}
+
+/// Collects the parameters of a query with `{value:insert(Struct)}` etc. arguments, which are
+/// evaluated once and returned by value (as `values`), see the `sql!` macro.
+#[inline]
+pub fn parameters<'a, V, const N: usize>(
+    values: &'a V,
+    f: impl FnOnce(&'a V) -> [&'a (dyn ToSql + Sync); N],
+) -> [&'a (dyn ToSql + Sync); N] {
+    f(values)
+}
+
 pub struct Valid<'a, B: 'a + ?Sized, O = B>(PhantomData<(&'a B, O)>);
 
 impl<'a, T> From<&'a T> for Valid<'a, T, T> {
@@ -158,6 +193,20 @@ where
     type SqlType = Vec<T::Type>;
 }
 
+impl<T> AsSqlType for Vec<Option<T>>
+where
+    T: SqlType,
+{
+    type SqlType = Vec<T::Type>;
+}
+
+impl<T> AsSqlType for Option<Vec<Option<T>>>
+where
+    T: SqlType,
+{
+    type SqlType = Vec<T::Type>;
+}
+
 // BYTEA
 impl AsSqlType for Vec<u8> {
     type SqlType = Bytea;
@@ -175,3 +224,164 @@ impl AsSqlType for Option<&[u8]> {
 impl AsSqlType for Vec<Vec<u8>> {
     type SqlType = Self;
 }
+
+/// A value of a map collected from the rows of a query, read together with its key: either the
+/// second of two columns (keyed by the first one), or a struct keyed by its `#[sqlm(key)]` field
+/// (implemented by the `FromRow` derive, see [`Keyed`]).
+///
+/// [`Keyed`]: crate::Keyed
+pub trait MapValue<Cols, K>: Sized {
+    fn from_row(row: tokio_postgres::Row) -> Result<(K, Self), Error>;
+}
+
+impl<K, V, Cols> MapValue<Pair<K::SqlType, V::SqlType, Cols>, K> for V
+where
+    K: AsSqlType + FromSqlOwned,
+    V: AsSqlType + FromSqlOwned,
+{
+    fn from_row(row: tokio_postgres::Row) -> Result<(K, Self), Error> {
+        Ok((try_get(&row, 0)?, try_get(&row, 1)?))
+    }
+}
+
+/// Ensures that the argument of e.g. `{user:insert(User)}` is a `User` (and derives [`ToRow`]), before
+/// its fields are used as parameters.
+pub fn to_row<T: ToRow>(row: &T) -> &T {
+    row
+}
+
+/// One column of `{rows:unnest(Struct)}`, i.e. the values of one field of all rows, written as an
+/// array.
+pub struct Unnest<'a, T>(pub Vec<&'a T>);
+
+pub fn unnest<'a, R: ToRow, T>(rows: &'a [R], field: impl Fn(&'a R) -> &'a T) -> Unnest<'a, T> {
+    Unnest(rows.iter().map(field).collect())
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Unnest<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: ToSql> ToSql for Unnest<'_, T> {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        self.0.to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <Vec<&T> as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+impl<T: AsSqlType> AsSqlType for Unnest<'_, T> {
+    type SqlType = Vec<T::SqlType>;
+}
+
+/// Wraps a parameter marked as secret (e.g. `{password:secret}`), so that its value is never
+/// recorded in traces.
+pub struct Secret<T>(pub T);
+
+impl<T> std::fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+impl<T: ToSql> ToSql for Secret<T> {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        self.0.to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        T::accepts(ty)
+    }
+
+    fn to_sql_checked(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        self.0.to_sql_checked(ty, out)
+    }
+}
+
+/// Reads or writes a value of a domain type (e.g. `CREATE DOMAIN email AS TEXT`) as its base type.
+///
+/// Parameters of a domain type are wrapped with this by the [`sql!`](crate::sql) macro, and all
+/// values read from rows go through it (as arrays of domains are returned as such).
+pub struct Domain<T>(pub T);
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Domain<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<'a, T: FromSql<'a>> FromSql<'a> for Domain<T> {
+    fn from_sql(
+        ty: &Type,
+        raw: &'a [u8],
+    ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        T::from_sql(&base_type(ty), raw).map(Self)
+    }
+
+    fn from_sql_null(ty: &Type) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        T::from_sql_null(&base_type(ty)).map(Self)
+    }
+
+    fn from_sql_nullable(
+        ty: &Type,
+        raw: Option<&'a [u8]>,
+    ) -> Result<Self, Box<dyn std::error::Error + Sync + Send>> {
+        T::from_sql_nullable(&base_type(ty), raw).map(Self)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        T::accepts(&base_type(ty))
+    }
+}
+
+impl<T: ToSql> ToSql for Domain<T> {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        // Arrays are written with the type of their elements, which must stay the domain.
+        match ty.kind() {
+            Kind::Domain(_) => self.0.to_sql(&base_type(ty), out),
+            _ => self.0.to_sql(ty, out),
+        }
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        T::accepts(&base_type(ty))
+    }
+
+    to_sql_checked!();
+}
+
+/// The base type of a domain (or of an array of a domain).
+fn base_type(ty: &Type) -> Cow<'_, Type> {
+    match ty.kind() {
+        Kind::Domain(base) => Cow::Owned(base_type(base).into_owned()),
+        Kind::Array(member) if matches!(member.kind(), Kind::Domain(_)) => Cow::Owned(Type::new(
+            ty.name().to_string(),
+            ty.oid(),
+            Kind::Array(base_type(member).into_owned()),
+            ty.schema().to_string(),
+        )),
+        _ => Cow::Borrowed(ty),
+    }
+}
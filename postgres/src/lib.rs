@@ -8,7 +8,7 @@
 //! # Example
 //!
 //! ```
-//! use sqlm_postgres::{sql, Enum, FromRow, FromSql, ToSql};
+//! use sqlm_postgres::{sql, FromRow, PgEnum};
 //!
 //! # #[tokio::main]
 //! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -22,13 +22,11 @@
 //!     role: Role,
 //! }
 //!
-//! #[derive(Debug, Default, FromSql, ToSql, Enum)]
-//! #[postgres(name = "role")]
+//! #[derive(Debug, Default, PgEnum)]
+//! #[sqlm(name = "role", rename_all = "lowercase")]
 //! enum Role {
 //!     #[default]
-//!     #[postgres(name = "user")]
 //!     User,
-//!     #[postgres(name = "admin")]
 //!     Admin,
 //! }
 //! # Ok(())
@@ -56,24 +54,26 @@ extern crate self as sqlm_postgres;
 mod connection;
 pub mod error;
 mod future;
+pub mod hook;
 #[doc(hidden)]
 pub mod internal;
 mod macros;
 pub mod pool;
 mod query;
 mod row;
-#[doc(hidden)]
+pub mod telemetry;
 pub mod types;
 
 use std::marker::PhantomData;
+use std::panic::Location;
 
 pub use connection::{Connection, Session, Transaction};
 use deadpool_postgres::ClientWrapper;
 pub use error::Error;
 pub use future::SqlFuture;
-pub use macros::{Enum, FromRow, sql};
+pub use macros::{Enum, FromRow, Newtype, PgEnum, ToRow, sql};
 use query::Query;
-pub use row::{FromRow, Row};
+pub use row::{FromRow, Keyed, Row, ToRow};
 pub use tokio_postgres;
 pub use tokio_postgres::types::{FromSql, ToSql};
 pub use types::SqlType;
@@ -124,6 +124,16 @@ pub async fn connect() -> Result<Session, Error> {
     pool.connect().await
 }
 
+/// Use the given pool for queries that are awaited without an explicit connection, instead of
+/// creating one from the `DATABASE_URL` env variable (e.g. to register
+/// [hooks](pool::Pool::with_hook)).
+///
+/// Returns the pool back as an error if a global pool has already been set or created.
+#[cfg(feature = "global_pool")]
+pub fn set_pool(pool: pool::Pool) -> Result<(), pool::Pool> {
+    POOL.set(pool)
+}
+
 /// The struct created by [`sql!`]; executed by calling `.await`.
 pub struct Sql<'a, Cols, T> {
     // Fields need to be public so that they can be set by the macro invocation.
@@ -132,10 +142,16 @@ pub struct Sql<'a, Cols, T> {
     #[doc(hidden)]
     pub parameters: &'a [&'a (dyn ToSql + Sync)],
     #[doc(hidden)]
+    pub parameter_types: &'static [&'static str],
+    #[doc(hidden)]
     pub transaction: Option<&'a Transaction<'a>>,
     #[doc(hidden)]
     pub connection: Option<&'a ClientWrapper>,
     #[doc(hidden)]
+    pub first: bool,
+    #[doc(hidden)]
+    pub location: &'static Location<'static>,
+    #[doc(hidden)]
     pub marker: PhantomData<(Cols, T)>,
 }
 
@@ -150,4 +166,45 @@ impl<'a, Cols, T> Sql<'a, Cols, T> {
     {
         SqlFuture::with_connection(self, conn)
     }
+
+    /// Take the first row if a query returns more than one row, instead of failing with
+    /// [`ErrorKind::TooManyRows`]. Only affects queries that read into a single struct or literal
+    /// (or an [`Option`] of those).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use sqlm_postgres::sql;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let name: Option<String> = sql!("SELECT name FROM users ORDER BY id")
+    ///     .first()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`ErrorKind::TooManyRows`]: error::ErrorKind::TooManyRows
+    pub fn first(mut self) -> Self {
+        self.first = true;
+        self
+    }
+
+    async fn query_one(&self, conn: impl Connection) -> Result<tokio_postgres::Row, Error> {
+        if self.first {
+            conn.query_first(self.query, self.parameters)
+                .await?
+                .ok_or_else(|| error::ErrorKind::RowNotFound.into())
+        } else {
+            conn.query_one(self.query, self.parameters).await
+        }
+    }
+
+    async fn query_opt(&self, conn: impl Connection) -> Result<Option<tokio_postgres::Row>, Error> {
+        if self.first {
+            conn.query_first(self.query, self.parameters).await
+        } else {
+            conn.query_opt(self.query, self.parameters).await
+        }
+    }
 }
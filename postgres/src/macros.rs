@@ -1,7 +1,12 @@
 /// A derive necessary to support compile checks between Postgres and Rust enums.
 ///
 /// In addition, enums also need to implement `tokio_postgres`'s [`FromSql`] and [`ToSql`], so it
-/// can be read from and written to Postgres.
+/// can be read from and written to Postgres. See [`PgEnum`] for a derive that generates all of
+/// them at once.
+///
+/// The enum is identified by its Postgres type name (taken from `#[postgres(name = "...")]`,
+/// defaulting to the name of the Rust enum) and schema (`#[sqlm(schema = "...")]`, defaulting to
+/// `public`), so it is not accepted for a different enum type with the same variants.
 ///
 /// # Example
 /// ```
@@ -26,12 +31,19 @@ pub use sqlm_postgres_macros::Enum;
 /// can of course use [`Option`] as its default is simply [`None`]).
 /// Alternatively, the default value can be set using a `#[sqlm(default = ...)]` attribute.
 ///
+/// Fields annotated with `#[sqlm(json)]` are read from a `JSON`/`JSONB` column and deserialized via
+/// serde (see [`Json`](crate::types::Json)).
+///
+/// A field annotated with `#[sqlm(key)]` is used as the key when collecting rows into a map (e.g.
+/// `HashMap<i64, User>`, see [`Keyed`](crate::Keyed)).
+///
 /// # Example
 ///
 /// ```
 /// # #[cfg(feature = "time")]
 /// #[derive(sqlm_postgres::FromRow)]
 /// struct User {
+///     #[sqlm(key)]
 ///     id: i64,
 ///     name: String,
 ///     #[sqlm(default = time::OffsetDateTime::UNIX_EPOCH)]
@@ -41,6 +53,84 @@ pub use sqlm_postgres_macros::Enum;
 ///
 /// [`FromRow`]: trait@crate::FromRow
 pub use sqlm_postgres_macros::FromRow;
+/// Derive [`SqlType`], [`FromSql`] and [`ToSql`] for a tuple struct with a single field, delegating
+/// all of them to the wrapped type.
+///
+/// # Example
+///
+/// ```
+/// #[derive(Debug, Default, sqlm_postgres::Newtype)]
+/// struct UserId(i64);
+/// ```
+///
+/// [`SqlType`]: crate::SqlType
+/// [`FromSql`]: crate::FromSql
+/// [`ToSql`]: crate::ToSql
+pub use sqlm_postgres_macros::Newtype;
+/// Derive [`SqlType`], [`FromSql`] and [`ToSql`] for a Postgres enum, without the need to also
+/// derive `tokio_postgres`'s [`FromSql`] and [`ToSql`].
+///
+/// The Postgres type name defaults to the name of the Rust enum and can be set via
+/// `#[sqlm(name = "...")]`, its schema (defaulting to `public`) via `#[sqlm(schema = "...")]`.
+/// Variant names can be set with `#[sqlm(name = "...")]` on the variant or
+/// with `#[sqlm(rename_all = "...")]` on the enum (same rules as `tokio_postgres`'s derive).
+///
+/// The following additional items can be opted into:
+/// - `#[sqlm(as_str)]`: an `as_str()` method returning the Postgres name of a variant
+/// - `#[sqlm(from_str)]`: a [`FromStr`](std::str::FromStr) implementation
+/// - `#[sqlm(display)]`: a [`Display`](std::fmt::Display) implementation
+///
+/// With `#[sqlm(text)]`, the enum is stored as the name of its variant in a `TEXT`/`VARCHAR` column
+/// instead of a Postgres enum (and is compile-time checked as a [`String`]).
+///
+/// # Example
+/// ```
+/// #[derive(Debug, Default, sqlm_postgres::PgEnum)]
+/// #[sqlm(name = "role", rename_all = "snake_case", as_str, from_str, display)]
+/// enum Role {
+///     #[default]
+///     User,
+///     Admin,
+/// }
+///
+/// #[derive(Debug, Default, sqlm_postgres::PgEnum)]
+/// #[sqlm(text, rename_all = "snake_case")]
+/// enum Visibility {
+///     #[default]
+///     Private,
+///     Public,
+/// }
+/// ```
+///
+/// [`SqlType`]: crate::SqlType
+/// [`FromSql`]: crate::FromSql
+/// [`ToSql`]: crate::ToSql
+pub use sqlm_postgres_macros::PgEnum;
+/// Derive [`ToRow`] for a struct, required to use its fields as query parameters via
+/// `{user:insert(User)}` or `{patch:set(UserPatch)}` (see [`sql!`](crate::sql)).
+///
+/// The fields are written to the columns of the same name. For `{rows:unnest(Struct)}`, the
+/// Postgres type of each field is derived from primitives (e.g. `i64` as `int8`) and fully
+/// qualified paths of common types (e.g. `time::OffsetDateTime`, but not a bare `OffsetDateTime`),
+/// and otherwise has to be set via `#[sqlm(pg_type = "...")]` (e.g. for enums).
+///
+/// # Example
+///
+/// ```
+/// # #[derive(Debug, sqlm_postgres::PgEnum)]
+/// # #[sqlm(name = "role", rename_all = "lowercase")]
+/// # enum Role { User, Admin }
+/// #[derive(sqlm_postgres::ToRow)]
+/// struct NewUser {
+///     name: String,
+///     email: Option<String>,
+///     #[sqlm(pg_type = "role")]
+///     role: Role,
+/// }
+/// ```
+///
+/// [`ToRow`]: trait@crate::ToRow
+pub use sqlm_postgres_macros::ToRow;
 /// Creates a parameterized, compile-time checked database query that accepts parameters similar to
 /// the [`format!`] macro.
 ///
@@ -48,11 +138,51 @@ pub use sqlm_postgres_macros::FromRow;
 /// accordingly.
 ///
 /// The returned type can either be a struct (that implements [`FromRow`]), a literal (string,
-/// integer, ...), or a [`Vec`] or [`Option`] of the former.
+/// integer, ...), or a [`Vec`] or [`Option`] of the former. In addition, results can be collected
+/// into:
+/// - a [`HashSet`], [`BTreeSet`] or `IndexSet` (with the `indexmap` feature) of a literal
+/// - a [`HashMap`], [`BTreeMap`] or `IndexMap` of literals, for queries returning two columns (the
+///   first one being the key, the second one the value)
+/// - a map of structs, keyed by the struct's field marked as `#[sqlm(key)]` (see
+///   [`FromRow`](derive@crate::FromRow)), for queries not returning exactly two columns
 ///
 /// A connection is automatically established, but also be explicitly set via
 /// [`Sql::run_with`].
 ///
+/// Besides variables (e.g. `{id}`), placeholders can contain field accesses, indexing and method
+/// calls (e.g. `{user.id}`, `{ids[0]}` or `{name.trim()}`). Each distinct placeholder becomes one
+/// parameter, so its expression is only evaluated once. Expressions can also start with a named
+/// argument (e.g. `{user.id}` with `user = ...`), whose expression is then substituted (and
+/// evaluated once per placeholder).
+///
+/// Parameters whose type Postgres cannot infer (e.g. `SELECT {x} IS NULL`) can be cast via
+/// `{x::int8}`, which also determines the type the parameter is checked against. Only the `::`
+/// form is accepted, as a single colon introduces a modifier (see below): with `{x:int8}` also
+/// being a cast, a misspelled modifier would be sent to Postgres as a cast instead of being
+/// reported as an unknown modifier.
+///
+/// Parameters containing sensitive data can be marked as secret (e.g. `{password:secret}`), which
+/// records them as `<redacted>` in traces (see [`telemetry`]). A cast goes before the modifier,
+/// e.g. `{password::text:secret}`.
+///
+/// `{User::*}` expands to the columns of a struct deriving [`FromRow`] (in the order of its
+/// fields), optionally prefixed with a table alias (e.g. `{u.User::*}`). The struct must be defined
+/// in the same crate, and either in the same module or referred to by its module path (e.g.
+/// `{models::User::*}`), as the columns are looked up via a hidden macro defined next to it.
+/// Field names are used as column names, with raw identifiers unescaped (`r#type` is `"type"`).
+///
+/// The fields of a struct deriving [`ToRow`] can be used as parameters, with the same scoping rules:
+/// - `{user:insert(NewUser)}` expands to `("name", ...) VALUES ($1, ...)`
+/// - `{patch:set(UserPatch)}` expands to `"name" = $1, ...`, with [`Option`] fields expanding to
+///   `"name" = COALESCE($1, "name")` instead, so fields that are [`None`] keep their current value
+///   (which means that a column cannot be set to `NULL` this way)
+/// - `{rows:unnest(NewUser)}` (with `rows` being a slice) expands to
+///   `SELECT * FROM UNNEST($1::text[], ...)`, passing one array per field, e.g. for bulk inserts
+///   via `INSERT INTO users (name, ...) {rows:unnest(NewUser)} ON CONFLICT ...`
+///
+/// The argument is evaluated once (not once per field). Queries using these have to be awaited (or
+/// run) in the statement they are created in, as the parameters borrow temporaries.
+///
 /// # Examples
 ///
 /// ```
@@ -60,10 +190,40 @@ pub use sqlm_postgres_macros::FromRow;
 /// # #[tokio::main]
 /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
 /// let name: String = sql!("SELECT name FROM users WHERE id = {id}", id = 1i64).await?;
+///
+/// let user = (1i64, "first");
+/// let name: Option<String> = sql!("SELECT name FROM users WHERE id = {user.0}").await?;
+///
+/// let password = "hunter2";
+/// let id: Option<i64> = sql!("SELECT id FROM users WHERE name = {password:secret}").await?;
+///
+/// #[derive(sqlm_postgres::FromRow)]
+/// struct User {
+///     id: i64,
+///     name: Option<String>,
+/// }
+///
+/// let users: Vec<User> = sql!("SELECT {u.User::*} FROM users u ORDER BY u.id").await?;
+///
+/// #[derive(sqlm_postgres::ToRow)]
+/// struct UserPatch {
+///     name: Option<String>,
+/// }
+///
+/// async fn rename(id: i64, name: Option<String>) -> Result<(), sqlm_postgres::Error> {
+///     let patch = UserPatch { name };
+///     sql!("UPDATE users SET {patch:set(UserPatch)} WHERE id = {id}").await
+/// }
 /// # Ok(())
 /// # }
 /// ```
 ///
+/// [`telemetry`]: crate::telemetry
+/// [`HashSet`]: std::collections::HashSet
+/// [`BTreeSet`]: std::collections::BTreeSet
+/// [`HashMap`]: std::collections::HashMap
+/// [`BTreeMap`]: std::collections::BTreeMap
 /// [`FromRow`]: super::FromRow
+/// [`ToRow`]: super::ToRow
 /// [`Sql::run_with`]: super::Sql::run_with
 pub use sqlm_postgres_macros::sql;
@@ -6,15 +6,28 @@ use tokio_postgres::NoTls;
 use tokio_postgres::config::SslMode;
 
 use crate::error::ErrorKind;
+use crate::hook::{Hooks, QueryHook};
+use crate::telemetry::ConnectionInfo;
 use crate::{Error, Session};
 
 #[derive(Clone)]
-pub struct Pool(deadpool_postgres::Pool);
+pub struct Pool(deadpool_postgres::Pool, Arc<PoolContext>);
+
+/// State shared by a pool and all of its connections.
+#[derive(Clone, Default)]
+pub(crate) struct PoolContext {
+    pub(crate) info: ConnectionInfo,
+    pub(crate) hooks: Hooks,
+}
 
 impl Pool {
     pub fn new(database_url: &str, pool_size: usize) -> Result<Self, Error> {
         let mut config = tokio_postgres::Config::from_str(database_url)?;
         config.application_name(env!("CARGO_PKG_NAME"));
+        let context = Arc::new(PoolContext {
+            info: ConnectionInfo::from_config(&config),
+            hooks: Hooks::default(),
+        });
 
         // TODO: take all possible SSL variants into account, see e.g.
         // https://github.com/jbg/tokio-postgres-rustls/issues/11
@@ -43,7 +56,7 @@ impl Pool {
         let pool = deadpool_postgres::Pool::builder(mgr)
             .max_size(pool_size)
             .build()?;
-        Ok(Self(pool))
+        Ok(Self(pool, context))
     }
 
     pub fn from_env(pool_size: usize) -> Result<Self, Error> {
@@ -55,12 +68,23 @@ impl Pool {
         )
     }
 
+    /// Register a hook that is called around every query executed on a connection of this pool
+    /// (see [`hook`](crate::hook)).
+    pub fn with_hook(mut self, hook: impl QueryHook) -> Self {
+        Arc::make_mut(&mut self.1).hooks.push(hook);
+        self
+    }
+
     #[tracing::instrument(skip_all)]
     pub async fn connect(&self) -> Result<Session, Error> {
         // Don't trace connect, as this would create an endless loop of connecting again and
         // again when persisting the connect trace!
+        #[cfg(feature = "metrics")]
+        crate::telemetry::record_pool_status(self.0.status());
         let conn = self.0.get().await?;
-        Ok(Session(conn))
+        #[cfg(feature = "metrics")]
+        crate::telemetry::record_pool_status(self.0.status());
+        Ok(Session(conn, Arc::clone(&self.1)))
     }
 }
 
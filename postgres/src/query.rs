@@ -1,11 +1,23 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::future::Future;
+use std::hash::Hash;
 use std::pin::Pin;
 
+#[cfg(feature = "indexmap")]
+use indexmap::{IndexMap, IndexSet};
 use tokio_postgres::types::{FromSqlOwned, ToSql};
 
-use crate::types::{Array, Bytea, Primitive, SqlType, Struct};
+use crate::internal::MapValue;
+use crate::row::try_get;
+use crate::types::{Array, Array2, Bytea, Pair, Primitive, SqlType, Struct};
 use crate::{Error, FromRow, Sql};
 
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be read from the rows returned by the query",
+    label = "the query returns `{Cols}`",
+    note = "column and enum variant names are spelled out as their characters, e.g. `N2<'i', 'd'>` \
+            for `id`"
+)]
 pub trait Query<Cols>: Sized {
     fn query<'a>(
         sql: &'a Sql<'a, Cols, Self>,
@@ -32,8 +44,8 @@ impl Query<Primitive<Bytea>> for Vec<u8> {
         conn: impl super::Connection + 'a,
     ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
         Box::pin(async move {
-            let row = conn.query_one(sql.query, sql.parameters).await?;
-            Ok(row.try_get(0)?)
+            let row = sql.query_one(conn).await?;
+            try_get(&row, 0)
         })
     }
 }
@@ -48,9 +60,9 @@ where
         conn: impl super::Connection + 'a,
     ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
         Box::pin(async move {
-            let row = conn.query_opt(sql.query, sql.parameters).await?;
+            let row = sql.query_opt(conn).await?;
             match row {
-                Some(row) => Ok(row.try_get::<'_, _, Option<T>>(0)?),
+                Some(row) => try_get::<_, Option<T>>(&row, 0),
                 None => Ok(None),
             }
         })
@@ -63,9 +75,9 @@ impl Query<Primitive<Bytea>> for Option<Vec<u8>> {
         conn: impl super::Connection + 'a,
     ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
         Box::pin(async move {
-            let row = conn.query_opt(sql.query, sql.parameters).await?;
+            let row = sql.query_opt(conn).await?;
             match row {
-                Some(row) => Ok(row.try_get::<'_, _, Self>(0)?),
+                Some(row) => try_get::<_, Self>(&row, 0),
                 None => Ok(None),
             }
         })
@@ -83,9 +95,23 @@ where
     ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
         Box::pin(async move {
             let rows = conn.query(sql.query, sql.parameters).await?;
-            rows.into_iter()
-                .map(|row| row.try_get(0).map_err(Error::from))
-                .collect()
+            rows.into_iter().map(|row| try_get(&row, 0)).collect()
+        })
+    }
+}
+
+impl<T> Query<Primitive<T::Type>> for Vec<Option<T>>
+where
+    T: SqlType + FromSqlOwned + ToSql + Send + Sync + 'static,
+    T::Type: Send + Sync + 'static,
+{
+    fn query<'a>(
+        sql: &'a Sql<'a, Primitive<T::Type>, Self>,
+        conn: impl super::Connection + 'a,
+    ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
+        Box::pin(async move {
+            let rows = conn.query(sql.query, sql.parameters).await?;
+            rows.into_iter().map(|row| try_get(&row, 0)).collect()
         })
     }
 }
@@ -97,9 +123,7 @@ impl Query<Primitive<Bytea>> for Vec<Vec<u8>> {
     ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
         Box::pin(async move {
             let rows = conn.query(sql.query, sql.parameters).await?;
-            rows.into_iter()
-                .map(|row| row.try_get(0).map_err(Error::from))
-                .collect()
+            rows.into_iter().map(|row| try_get(&row, 0)).collect()
         })
     }
 }
@@ -114,77 +138,185 @@ where
         conn: impl super::Connection + 'a,
     ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
         Box::pin(async move {
-            let row = conn.query_one(sql.query, sql.parameters).await?;
-            Ok(row.try_get(0)?)
+            let row = sql.query_one(conn).await?;
+            try_get(&row, 0)
         })
     }
 }
 
-impl Query<Array<Vec<Bytea>>> for Vec<Vec<u8>> {
+impl<T> Query<Array<Vec<T::Type>>> for Vec<Option<T>>
+where
+    T: SqlType + FromSqlOwned + ToSql + Send + Sync + 'static,
+    T::Type: Send + Sync + 'static,
+{
     fn query<'a>(
-        sql: &'a Sql<'a, Array<Vec<Bytea>>, Self>,
+        sql: &'a Sql<'a, Array<Vec<T::Type>>, Self>,
         conn: impl super::Connection + 'a,
     ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
         Box::pin(async move {
-            let row = conn.query_one(sql.query, sql.parameters).await?;
-            Ok(row.try_get(0)?)
+            let row = sql.query_one(conn).await?;
+            try_get(&row, 0)
         })
     }
 }
 
-impl<T, Cols> Query<Struct<Cols>> for T
+impl<T> Query<Array<Vec<T::Type>>> for Array2<T>
 where
-    Cols: Send + Sync,
-    T: FromRow<Struct<Cols>> + Send + Sync,
+    T: SqlType + FromSqlOwned + ToSql + Send + Sync + 'static,
+    T::Type: Send + Sync + 'static,
 {
     fn query<'a>(
-        sql: &'a Sql<'a, Struct<Cols>, Self>,
+        sql: &'a Sql<'a, Array<Vec<T::Type>>, Self>,
         conn: impl super::Connection + 'a,
     ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
         Box::pin(async move {
-            let row = conn.query_one(sql.query, sql.parameters).await?;
-            Ok(FromRow::<Struct<Cols>>::from_row(row.into())?)
+            let row = sql.query_one(conn).await?;
+            try_get(&row, 0)
         })
     }
 }
 
-impl<T, Cols> Query<Struct<Cols>> for Option<T>
+impl<T> Query<Array<Vec<T::Type>>> for Array2<Option<T>>
 where
-    Cols: Send + Sync,
-    T: FromRow<Struct<Cols>> + Send + Sync,
+    T: SqlType + FromSqlOwned + ToSql + Send + Sync + 'static,
+    T::Type: Send + Sync + 'static,
 {
     fn query<'a>(
-        sql: &'a Sql<'a, Struct<Cols>, Self>,
+        sql: &'a Sql<'a, Array<Vec<T::Type>>, Self>,
         conn: impl super::Connection + 'a,
     ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
         Box::pin(async move {
-            let row = conn.query_opt(sql.query, sql.parameters).await?;
-            match row {
-                Some(row) => Ok(Some(FromRow::<Struct<Cols>>::from_row(row.into())?)),
-                None => Ok(None),
-            }
+            let row = sql.query_one(conn).await?;
+            try_get(&row, 0)
         })
     }
 }
 
-impl<T, Cols> Query<Struct<Cols>> for Vec<T>
-where
-    Cols: Send + Sync,
-    T: FromRow<Struct<Cols>> + Send + Sync,
-{
+impl Query<Array<Vec<Bytea>>> for Vec<Vec<u8>> {
     fn query<'a>(
-        sql: &'a Sql<'a, Struct<Cols>, Self>,
+        sql: &'a Sql<'a, Array<Vec<Bytea>>, Self>,
         conn: impl super::Connection + 'a,
     ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
         Box::pin(async move {
-            let rows = conn.query(sql.query, sql.parameters).await?;
-            rows.into_iter()
-                .map(|row| FromRow::<Struct<Cols>>::from_row(row.into()).map_err(Error::from))
-                .collect()
+            let row = sql.query_one(conn).await?;
+            try_get(&row, 0)
         })
     }
 }
 
+macro_rules! impl_struct {
+    ($marker:ty, $($param:ident),+) => {
+        impl<T, $($param),+> Query<$marker> for T
+        where
+            $($param: Send + Sync,)+
+            T: FromRow<Struct<Cols>> + Send + Sync,
+        {
+            fn query<'a>(
+                sql: &'a Sql<'a, $marker, Self>,
+                conn: impl super::Connection + 'a,
+            ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
+                Box::pin(async move {
+                    let row = sql.query_one(conn).await?;
+                    FromRow::<Struct<Cols>>::from_row(row.into())
+                })
+            }
+        }
+
+        impl<T, $($param),+> Query<$marker> for Option<T>
+        where
+            $($param: Send + Sync,)+
+            T: FromRow<Struct<Cols>> + Send + Sync,
+        {
+            fn query<'a>(
+                sql: &'a Sql<'a, $marker, Self>,
+                conn: impl super::Connection + 'a,
+            ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
+                Box::pin(async move {
+                    let row = sql.query_opt(conn).await?;
+                    match row {
+                        Some(row) => Ok(Some(FromRow::<Struct<Cols>>::from_row(row.into())?)),
+                        None => Ok(None),
+                    }
+                })
+            }
+        }
+
+        impl<T, $($param),+> Query<$marker> for Vec<T>
+        where
+            $($param: Send + Sync,)+
+            T: FromRow<Struct<Cols>> + Send + Sync,
+        {
+            fn query<'a>(
+                sql: &'a Sql<'a, $marker, Self>,
+                conn: impl super::Connection + 'a,
+            ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
+                Box::pin(async move {
+                    let rows = conn.query(sql.query, sql.parameters).await?;
+                    rows.into_iter()
+                        .map(|row| FromRow::<Struct<Cols>>::from_row(row.into()))
+                        .collect()
+                })
+            }
+        }
+    };
+}
+
+impl_struct!(Struct<Cols>, Cols);
+impl_struct!(Pair<K, V, Cols>, K, V, Cols);
+
+macro_rules! impl_set {
+    ($set:ident, $($bound:tt)+) => {
+        impl<T> Query<Primitive<T::Type>> for $set<T>
+        where
+            T: SqlType + FromSqlOwned + ToSql + $($bound)+ + Send + Sync + 'static,
+            T::Type: Send + Sync + 'static,
+        {
+            fn query<'a>(
+                sql: &'a Sql<'a, Primitive<T::Type>, Self>,
+                conn: impl super::Connection + 'a,
+            ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
+                Box::pin(async move {
+                    let rows = conn.query(sql.query, sql.parameters).await?;
+                    rows.into_iter().map(|row| try_get(&row, 0)).collect()
+                })
+            }
+        }
+    };
+}
+
+impl_set!(HashSet, Eq + Hash);
+impl_set!(BTreeSet, Ord);
+#[cfg(feature = "indexmap")]
+impl_set!(IndexSet, Eq + Hash);
+
+macro_rules! impl_map {
+    ($map:ident, $($bound:tt)+) => {
+        /// Either two columns (the first one being the key), or structs keyed by their
+        /// `#[sqlm(key)]` field (see [`MapValue`]).
+        impl<K, V, Cols> Query<Cols> for $map<K, V>
+        where
+            Cols: Send + Sync,
+            K: $($bound)+ + Send + Sync,
+            V: MapValue<Cols, K> + Send + Sync,
+        {
+            fn query<'a>(
+                sql: &'a Sql<'a, Cols, Self>,
+                conn: impl super::Connection + 'a,
+            ) -> Pin<Box<dyn Future<Output = Result<Self, Error>> + Send + 'a>> {
+                Box::pin(async move {
+                    let rows = conn.query(sql.query, sql.parameters).await?;
+                    rows.into_iter().map(V::from_row).collect()
+                })
+            }
+        }
+    };
+}
+
+impl_map!(HashMap, Eq + Hash);
+impl_map!(BTreeMap, Ord);
+#[cfg(feature = "indexmap")]
+impl_map!(IndexMap, Eq + Hash);
+
 impl Query<()> for () {
     fn query<'a>(
         sql: &'a Sql<'a, (), Self>,
@@ -1,6 +1,15 @@
+use std::fmt;
 use std::marker::PhantomData;
 use std::ops::Deref;
 
+use tokio_postgres::Column;
+use tokio_postgres::row::RowIndex;
+use tokio_postgres::types::FromSql;
+
+use crate::Error;
+use crate::error::ErrorKind;
+use crate::internal::Domain;
+
 /// A row of data returned from Postgres.
 pub struct Row<Cols> {
     row: tokio_postgres::Row,
@@ -12,9 +21,102 @@ pub struct Row<Cols> {
 ///
 /// This is usually derived via [`FromRow`] and not implemented manually.
 ///
+/// Note that [`FromRow::from_row`] returns this crate's [`Error`] (it used to return a
+/// [`tokio_postgres::Error`]), so that decode errors can name the column. Manual implementations
+/// need to be updated; [`Row::try_get`] already returns the right error type, and a
+/// [`tokio_postgres::Error`] converts via `?`.
+///
 /// [`FromRow`]: `derive@crate::FromRow`
+#[diagnostic::on_unimplemented(
+    message = "the columns returned by the query don't match the fields of `{Self}`",
+    label = "the query returns `{Cols}`",
+    note = "the query must return exactly one column (of a compatible type) per field of `{Self}`"
+)]
 pub trait FromRow<Cols>: Sized {
-    fn from_row(row: Row<Cols>) -> Result<Self, tokio_postgres::Error>;
+    fn from_row(row: Row<Cols>) -> Result<Self, Error>;
+}
+
+/// A struct with a field marked as `#[sqlm(key)]`, whose value is used as the key when collecting
+/// rows into a map (e.g. `HashMap<i64, User>`).
+///
+/// This is derived via [`FromRow`] and not implemented manually.
+///
+/// [`FromRow`]: `derive@crate::FromRow`
+pub trait Keyed {
+    type Key;
+
+    fn key(&self) -> Self::Key;
+}
+
+/// A marker trait for structs whose fields can be used as query parameters, e.g. via
+/// `{user:insert(User)}` or `{patch:set(UserPatch)}` (see [`sql!`](crate::sql)).
+///
+/// This is derived via [`ToRow`] and not implemented manually.
+///
+/// [`ToRow`]: `derive@crate::ToRow`
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be used as query parameters",
+    label = "`{Self}` doesn't derive `ToRow`"
+)]
+pub trait ToRow {}
+
+impl<Cols> Row<Cols> {
+    /// Deserializes a value from the row, see [`tokio_postgres::Row::try_get`].
+    ///
+    /// In contrast to [`tokio_postgres::Row::try_get`], errors contain the name of the column
+    /// (see [`ErrorKind::Decode`]).
+    pub fn try_get<'a, I, T>(&'a self, idx: I) -> Result<T, Error>
+    where
+        I: ColumnIndex,
+        T: FromSql<'a>,
+    {
+        try_get(&self.row, idx)
+    }
+}
+
+/// A column position (`usize`) or name (`&str`), used to name the column in decode errors.
+///
+/// This trait is sealed (not exported) and only implemented for the same types as
+/// [`RowIndex`].
+pub trait ColumnIndex: RowIndex + fmt::Display {
+    fn column_name(&self, columns: &[Column]) -> String;
+}
+
+impl ColumnIndex for usize {
+    fn column_name(&self, columns: &[Column]) -> String {
+        match columns.get(*self) {
+            Some(column) => column.name().to_string(),
+            None => self.to_string(),
+        }
+    }
+}
+
+impl ColumnIndex for str {
+    fn column_name(&self, _columns: &[Column]) -> String {
+        self.to_string()
+    }
+}
+
+impl<T> ColumnIndex for &T
+where
+    T: ColumnIndex + ?Sized,
+{
+    fn column_name(&self, columns: &[Column]) -> String {
+        T::column_name(self, columns)
+    }
+}
+
+pub(crate) fn try_get<'a, I, T>(row: &'a tokio_postgres::Row, idx: I) -> Result<T, Error>
+where
+    I: ColumnIndex,
+    T: FromSql<'a>,
+{
+    row.try_get(&idx)
+        .map(|Domain(value)| value)
+        .map_err(|source| {
+            let column = idx.column_name(row.columns());
+            ErrorKind::Decode { column, source }.into()
+        })
 }
 
 impl<Cols> Deref for Row<Cols> {
@@ -0,0 +1,333 @@
+//! Settings for the tracing spans (and metrics) emitted for each query.
+//!
+//! Each query created with [`sql!`](crate::sql) is executed inside of a `sql query` debug span,
+//! with the following fields (following the OpenTelemetry database semantic conventions):
+//!
+//! - `db.system`: always `postgresql`
+//! - `db.statement`: the query
+//! - `db.operation`: the first keyword of the query (e.g. `SELECT`)
+//! - `db.name`, `server.address`: the database and host of the connection (if known)
+//! - `parameters`: the query parameters (see [`set_parameter_recording`]); parameters marked as
+//!   secret in the query (e.g. `{password:secret}`) are always recorded as `<redacted>`
+//! - `db.response.returned_rows`: the number of rows returned by the query
+//! - `db.response.affected_rows`: the number of rows affected by a statement without a result
+//! - `db.response.status_code`: the SQLSTATE code of a failed query
+//! - `otel.status_code`: `ERROR` if the query failed
+//!
+//! With the `metrics` feature enabled, the following metrics are emitted via the [`metrics`]
+//! crate:
+//!
+//! - `db.client.operation.duration` (histogram, in seconds), labeled by `db.system`,
+//!   `db.operation` and (for failed queries) `error.type`
+//! - `db.client.connection.count` (gauge), labeled by `state` (`idle` or `used`)
+//! - `db.client.connection.max` (gauge)
+//! - `db.client.connection.pending_requests` (gauge)
+//!
+//! Queries taking longer than the threshold configured via [`set_slow_query_threshold`] are
+//! logged as a `slow sql query` warning, including the location of the [`sql!`](crate::sql)
+//! invocation. In debug builds, [`set_explain_slow_queries`] additionally attaches the plan of
+//! slow `SELECT` statements.
+//!
+//! [`metrics`]: https://docs.rs/metrics
+
+use std::fmt;
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+use std::time::Duration;
+
+use tokio_postgres::types::ToSql;
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
+
+use crate::{Connection, Error};
+
+static PARAMETER_RECORDING: AtomicU8 = AtomicU8::new(ParameterRecording::Values as u8);
+static SLOW_QUERY_THRESHOLD: AtomicU64 = AtomicU64::new(u64::MAX);
+static EXPLAIN_SLOW_QUERIES: AtomicBool = AtomicBool::new(false);
+
+/// How query parameters are recorded on the `sql query` span.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+#[non_exhaustive]
+pub enum ParameterRecording {
+    /// Record the [`Debug`] output of all parameters (except for the ones marked as secret).
+    #[default]
+    Values,
+    /// Record the Postgres type of each parameter (e.g. `int8`) instead of its value.
+    Types,
+    /// Record `<redacted>` for each parameter.
+    Redacted,
+    /// Don't record parameters at all.
+    Off,
+}
+
+/// Set how query parameters are recorded on the `sql query` span (defaults to
+/// [`ParameterRecording::Values`]).
+///
+/// # Example
+///
+/// ```
+/// use sqlm_postgres::telemetry::{self, ParameterRecording};
+///
+/// telemetry::set_parameter_recording(ParameterRecording::Redacted);
+/// ```
+pub fn set_parameter_recording(recording: ParameterRecording) {
+    PARAMETER_RECORDING.store(recording as u8, Ordering::Relaxed);
+}
+
+/// The currently configured [`ParameterRecording`].
+pub fn parameter_recording() -> ParameterRecording {
+    match PARAMETER_RECORDING.load(Ordering::Relaxed) {
+        0 => ParameterRecording::Values,
+        1 => ParameterRecording::Types,
+        2 => ParameterRecording::Redacted,
+        _ => ParameterRecording::Off,
+    }
+}
+
+/// Log a warning for each query that takes at least `threshold` to complete (disabled by
+/// default; pass `None` to disable it again).
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+///
+/// sqlm_postgres::telemetry::set_slow_query_threshold(Some(Duration::from_millis(500)));
+/// ```
+pub fn set_slow_query_threshold(threshold: Option<Duration>) {
+    let nanos = threshold.map_or(u64::MAX, |threshold| {
+        u64::try_from(threshold.as_nanos()).unwrap_or(u64::MAX)
+    });
+    SLOW_QUERY_THRESHOLD.store(nanos, Ordering::Relaxed);
+}
+
+/// The currently configured slow query threshold.
+pub fn slow_query_threshold() -> Option<Duration> {
+    match SLOW_QUERY_THRESHOLD.load(Ordering::Relaxed) {
+        u64::MAX => None,
+        nanos => Some(Duration::from_nanos(nanos)),
+    }
+}
+
+/// Run `EXPLAIN` for slow `SELECT` statements and attach the estimated plan to the slow query
+/// warning (disabled by default).
+///
+/// The statement is only planned, not executed a second time (i.e. no `ANALYZE`), so functions
+/// with side effects called by the query don't run again. It only has an effect in debug builds.
+pub fn set_explain_slow_queries(enabled: bool) {
+    EXPLAIN_SLOW_QUERIES.store(enabled, Ordering::Relaxed);
+}
+
+/// Database and server a connection is connected to.
+#[doc(hidden)]
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionInfo {
+    db_name: Option<String>,
+    server_address: Option<String>,
+}
+
+impl ConnectionInfo {
+    pub(crate) fn from_config(config: &tokio_postgres::Config) -> Self {
+        Self {
+            db_name: config.get_dbname().map(str::to_string),
+            server_address: config.get_hosts().first().map(|host| match host {
+                tokio_postgres::config::Host::Tcp(host) => host.clone(),
+                #[cfg(unix)]
+                tokio_postgres::config::Host::Unix(path) => path.display().to_string(),
+            }),
+        }
+    }
+
+    pub(crate) fn record(&self, span: &Span) {
+        if let Some(db_name) = &self.db_name {
+            span.record("db.name", db_name.as_str());
+        }
+        if let Some(server_address) = &self.server_address {
+            span.record("server.address", server_address.as_str());
+        }
+    }
+}
+
+pub(crate) fn query_span(
+    query: &str,
+    parameters: &[&(dyn ToSql + Sync)],
+    parameter_types: &[&str],
+) -> Span {
+    let span = tracing::debug_span!(
+        "sql query",
+        db.system = "postgresql",
+        db.statement = query,
+        db.operation = operation(query),
+        db.name = Empty,
+        server.address = Empty,
+        parameters = Empty,
+        db.response.returned_rows = Empty,
+        db.response.affected_rows = Empty,
+        db.response.status_code = Empty,
+        otel.status_code = Empty,
+    );
+    match parameter_recording() {
+        ParameterRecording::Values => {
+            span.record("parameters", tracing::field::debug(parameters));
+        }
+        ParameterRecording::Types => {
+            span.record("parameters", tracing::field::debug(parameter_types));
+        }
+        ParameterRecording::Redacted => {
+            span.record(
+                "parameters",
+                tracing::field::debug(Redacted(parameters.len())),
+            );
+        }
+        ParameterRecording::Off => {}
+    }
+    span
+}
+
+/// Record the number of rows returned on the current `sql query` span.
+pub(crate) fn record_rows(count: usize) {
+    Span::current().record("db.response.returned_rows", count);
+}
+
+/// Record the number of rows affected by a statement on the current `sql query` span.
+pub(crate) fn record_affected_rows(count: u64) {
+    Span::current().record("db.response.affected_rows", count);
+}
+
+/// Record the outcome of a query on the current `sql query` span (and emit metrics).
+pub(crate) fn query_finished<T>(query: &str, elapsed: Duration, result: &Result<T, Error>) {
+    tracing::trace!(?elapsed, "sql query finished");
+
+    let error_type = match result {
+        Ok(_) => None,
+        Err(err) => {
+            let span = Span::current();
+            span.record("otel.status_code", "ERROR");
+            let code = err.code().map(|code| code.code());
+            if let Some(code) = code {
+                span.record("db.response.status_code", code);
+            }
+            Some(code.unwrap_or("other").to_string())
+        }
+    };
+
+    #[cfg(feature = "metrics")]
+    {
+        let mut labels = vec![
+            metrics::Label::new("db.system", "postgresql"),
+            metrics::Label::new("db.operation", operation(query).unwrap_or("")),
+        ];
+        if let Some(error_type) = error_type {
+            labels.push(metrics::Label::new("error.type", error_type));
+        }
+        metrics::histogram!("db.client.operation.duration", labels).record(elapsed.as_secs_f64());
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (query, error_type);
+}
+
+/// Log a warning if the query took longer than the configured slow query threshold.
+pub(crate) async fn check_slow_query(
+    conn: &impl Connection,
+    query: &str,
+    parameters: &[&(dyn ToSql + Sync)],
+    elapsed: Duration,
+    location: &Location<'static>,
+) {
+    if slow_query_threshold().is_none_or(|threshold| elapsed < threshold) {
+        return;
+    }
+
+    let plan = if cfg!(debug_assertions)
+        && EXPLAIN_SLOW_QUERIES.load(Ordering::Relaxed)
+        && operation(query) == Some("SELECT")
+    {
+        explain(conn, query, parameters).await
+    } else {
+        None
+    };
+
+    tracing::warn!(
+        db.statement = query,
+        ?elapsed,
+        %location,
+        plan,
+        "slow sql query"
+    );
+}
+
+async fn explain(
+    conn: &impl Connection,
+    query: &str,
+    parameters: &[&(dyn ToSql + Sync)],
+) -> Option<String> {
+    let explain = format!("EXPLAIN {query}");
+    let rows = conn
+        .query(&explain, parameters)
+        .instrument(tracing::debug_span!("sql explain"))
+        .await;
+    match rows {
+        Ok(rows) => {
+            let lines = rows
+                .iter()
+                .map(|row| row.try_get::<_, &str>(0))
+                .collect::<Result<Vec<_>, _>>();
+            match lines {
+                Ok(lines) => Some(lines.join("\n")),
+                Err(err) => {
+                    tracing::debug!(%err, "failed to read slow query plan");
+                    None
+                }
+            }
+        }
+        Err(err) => {
+            tracing::debug!(%err, "failed to explain slow query");
+            None
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+pub(crate) fn record_pool_status(status: deadpool_postgres::Status) {
+    metrics::gauge!("db.client.connection.count", "state" => "idle").set(status.available as f64);
+    metrics::gauge!("db.client.connection.count", "state" => "used")
+        .set(status.size.saturating_sub(status.available) as f64);
+    metrics::gauge!("db.client.connection.max").set(status.max_size as f64);
+    metrics::gauge!("db.client.connection.pending_requests").set(status.waiting as f64);
+}
+
+/// The operation (first keyword) of the query.
+fn operation(query: &str) -> Option<&'static str> {
+    const OPERATIONS: &[&str] = &[
+        "SELECT", "INSERT", "UPDATE", "DELETE", "WITH", "MERGE", "CALL", "VALUES", "COPY",
+        "CREATE", "ALTER", "DROP", "TRUNCATE", "SET", "SHOW", "LOCK", "EXPLAIN",
+    ];
+    let keyword = query
+        .trim_start_matches(|c: char| c.is_whitespace() || c == '(')
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .next()?;
+    OPERATIONS
+        .iter()
+        .find(|op| op.eq_ignore_ascii_case(keyword))
+        .copied()
+}
+
+struct Redacted(usize);
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(std::iter::repeat_n(RedactedValue, self.0))
+            .finish()
+    }
+}
+
+#[derive(Clone, Copy)]
+struct RedactedValue;
+
+impl fmt::Debug for RedactedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
@@ -1,8 +1,13 @@
+use std::error;
 use std::future::Future;
 use std::marker::PhantomData;
+use std::ops::Bound;
 use std::pin::Pin;
 
-use tokio_postgres::types::{FromSqlOwned, ToSql};
+use bytes::BytesMut;
+use fallible_iterator::FallibleIterator;
+use postgres_protocol::types::{self as protocol, ArrayDimension, RangeBound};
+use tokio_postgres::types::{FromSql, FromSqlOwned, IsNull, Kind, ToSql, Type, to_sql_checked};
 
 use crate::{Error, Sql};
 
@@ -11,7 +16,8 @@ use crate::{Error, Sql};
 /// # Example
 ///
 /// This can be useful to implement manually when e.g. reading a Postgres string column into an
-/// enum.
+/// enum (though for this simple case, [`PgEnum`](crate::PgEnum) with `#[sqlm(text)]` generates
+/// the same).
 ///
 /// ```
 /// #[derive(Debug, Default, Clone, Copy)]
@@ -111,8 +117,8 @@ pub trait SqlType {
         Self::Type: Send + Sync,
     {
         Box::pin(async move {
-            let row = conn.query_one(sql.query, sql.parameters).await?;
-            Ok(row.try_get(0)?)
+            let row = sql.query_one(conn).await?;
+            crate::row::try_get(&row, 0)
         })
     }
 }
@@ -120,24 +126,506 @@ pub trait SqlType {
 #[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Bytea(pub Vec<u8>);
 
+#[doc(hidden)]
 #[cfg(not(nightly_column_names))]
-pub struct StructColumn<T, const NAME: usize>(PhantomData<T>);
+pub struct StructColumn<T, Name>(PhantomData<(T, Name)>);
+#[doc(hidden)]
 #[cfg(nightly_column_names)]
 pub struct StructColumn<T, const NAME: &'static str>(PhantomData<T>);
 
+#[doc(hidden)]
 pub struct Struct<T>(PhantomData<T>);
 
+/// A query returning exactly two columns, which can be read like a [`Struct`] with the columns
+/// `Cols`, but also collected into a map (e.g. `HashMap<i64, String>`). `K` and `V` are the types
+/// of the two columns, in the order they are selected.
+#[doc(hidden)]
+pub struct Pair<K, V, Cols>(PhantomData<(K, V, Cols)>);
+
+#[doc(hidden)]
 pub struct Primitive<T>(PhantomData<T>);
 
+#[doc(hidden)]
 pub struct Array<T>(PhantomData<T>);
 
-pub struct Enum<T>(PhantomData<T>);
+// Enums are identified by their schema-qualified type name (e.g. `public.role`) and their sorted
+// variants, so that two enum types with the same labels aren't interchangeable.
+#[doc(hidden)]
+#[cfg(not(nightly_column_names))]
+pub struct Enum<Name, Variants>(PhantomData<(Name, Variants)>);
+#[doc(hidden)]
+#[cfg(nightly_column_names)]
+pub struct Enum<const NAME: &'static str, Variants>(PhantomData<Variants>);
 
+#[doc(hidden)]
 #[cfg(not(nightly_column_names))]
-pub struct EnumVariant<const NAME: usize>(());
+pub struct EnumVariant<Name>(PhantomData<Name>);
+#[doc(hidden)]
 #[cfg(nightly_column_names)]
 pub struct EnumVariant<const NAME: &'static str>(());
 
+// On stable Rust, column and variant names are encoded as types holding their characters (as
+// `&'static str` const generics require nightly), which keeps the names readable in type errors,
+// e.g. `N5<'e', 'm', 'a', 'i', 'l'>`. Names longer than 32 chars are split into a tuple of chunks.
+#[cfg(not(nightly_column_names))]
+macro_rules! names {
+    ($($name:ident<$($c:ident),+>;)*) => {
+        $(
+            #[doc(hidden)]
+            pub struct $name<$(const $c: char),+>(());
+        )*
+    };
+}
+
+#[cfg(not(nightly_column_names))]
+names! {
+    N1<C1>;
+    N2<C1, C2>;
+    N3<C1, C2, C3>;
+    N4<C1, C2, C3, C4>;
+    N5<C1, C2, C3, C4, C5>;
+    N6<C1, C2, C3, C4, C5, C6>;
+    N7<C1, C2, C3, C4, C5, C6, C7>;
+    N8<C1, C2, C3, C4, C5, C6, C7, C8>;
+    N9<C1, C2, C3, C4, C5, C6, C7, C8, C9>;
+    N10<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10>;
+    N11<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11>;
+    N12<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12>;
+    N13<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13>;
+    N14<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14>;
+    N15<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15>;
+    N16<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16>;
+    N17<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17>;
+    N18<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18>;
+    N19<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19>;
+    N20<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20>;
+    N21<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21>;
+    N22<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21, C22>;
+    N23<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21, C22, C23>;
+    N24<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21, C22, C23, C24>;
+    N25<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21, C22, C23, C24, C25>;
+    N26<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21, C22, C23, C24, C25, C26>;
+    N27<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21, C22, C23, C24, C25, C26, C27>;
+    N28<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21, C22, C23, C24, C25, C26, C27, C28>;
+    N29<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21, C22, C23, C24, C25, C26, C27, C28, C29>;
+    N30<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21, C22, C23, C24, C25, C26, C27, C28, C29, C30>;
+    N31<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21, C22, C23, C24, C25, C26, C27, C28, C29, C30, C31>;
+    N32<C1, C2, C3, C4, C5, C6, C7, C8, C9, C10, C11, C12, C13, C14, C15, C16, C17, C18, C19, C20, C21, C22, C23, C24, C25, C26, C27, C28, C29, C30, C31, C32>;
+}
+
+/// The error returned when reading or parsing a value that doesn't match any variant of an enum
+/// derived with [`PgEnum`](crate::PgEnum).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidVariant {
+    type_name: &'static str,
+    value: String,
+}
+
+impl InvalidVariant {
+    #[doc(hidden)]
+    pub fn new(type_name: &'static str, value: &str) -> Self {
+        Self {
+            type_name,
+            value: value.to_string(),
+        }
+    }
+
+    /// The value that didn't match any variant.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+}
+
+impl std::fmt::Display for InvalidVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid variant `{}` for enum `{}`",
+            self.value, self.type_name
+        )
+    }
+}
+
+impl error::Error for InvalidVariant {}
+
+/// A two-dimensional Postgres array (e.g. `INT[][]`), stored as its dimensions and the elements in
+/// row-major order.
+///
+/// Postgres doesn't distinguish between the number of dimensions of array types, which is why
+/// `Array2<T>` is compile-time checked like a [`Vec<T>`].
+///
+/// # Example
+///
+/// ```
+/// # use sqlm_postgres::{sql, types::Array2};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let matrix: Array2<i32> = sql!("SELECT '{{{{1,2,3}},{{4,5,6}}}}'::INT[]").await?;
+/// assert_eq!(matrix.dimensions(), (2, 3));
+/// assert_eq!(matrix.get(1, 0), Some(&4));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Array2<T> {
+    rows: usize,
+    columns: usize,
+    data: Vec<T>,
+}
+
+impl<T> Array2<T> {
+    /// Create a new two-dimensional array from its elements in row-major order.
+    ///
+    /// Returns [`None`] if the number of elements doesn't match the given dimensions.
+    pub fn from_vec(rows: usize, columns: usize, data: Vec<T>) -> Option<Self> {
+        if rows.checked_mul(columns)? != data.len() {
+            return None;
+        }
+
+        Some(Self {
+            rows,
+            columns,
+            data,
+        })
+    }
+
+    /// The number of rows and columns.
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.rows, self.columns)
+    }
+
+    /// The element at the given (zero-based) row and column.
+    pub fn get(&self, row: usize, column: usize) -> Option<&T> {
+        if row >= self.rows || column >= self.columns {
+            return None;
+        }
+
+        self.data.get(row * self.columns + column)
+    }
+
+    /// An iterator over the rows of the array.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.data.chunks(self.columns.max(1)).take(self.rows)
+    }
+
+    /// The elements in row-major order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Consume the array, returning its elements in row-major order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+}
+
+impl<T> Default for Array2<T> {
+    fn default() -> Self {
+        Self {
+            rows: 0,
+            columns: 0,
+            data: Vec::new(),
+        }
+    }
+}
+
+impl<'a, T> FromSql<'a> for Array2<T>
+where
+    T: FromSql<'a>,
+{
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn error::Error + Sync + Send>> {
+        let Kind::Array(member) = ty.kind() else {
+            return Err(format!("expected an array type, got {ty}").into());
+        };
+
+        let array = protocol::array_from_sql(raw)?;
+        let mut dimensions = array.dimensions();
+        let (rows, columns) = match (dimensions.next()?, dimensions.next()?, dimensions.next()?) {
+            (None, _, _) => (0, 0),
+            (Some(rows), Some(columns), None) => {
+                (usize::try_from(rows.len)?, usize::try_from(columns.len)?)
+            }
+            _ => return Err("expected a two-dimensional array".into()),
+        };
+
+        let data = array
+            .values()
+            .map(|v| T::from_sql_nullable(member, v))
+            .collect()?;
+
+        Ok(Self {
+            rows,
+            columns,
+            data,
+        })
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        match ty.kind() {
+            Kind::Array(member) => T::accepts(member),
+            _ => false,
+        }
+    }
+}
+
+impl<T> ToSql for Array2<T>
+where
+    T: ToSql,
+{
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn error::Error + Sync + Send>> {
+        let Kind::Array(member) = ty.kind() else {
+            return Err(format!("expected an array type, got {ty}").into());
+        };
+
+        let dimensions = [
+            ArrayDimension {
+                len: i32::try_from(self.rows)?,
+                lower_bound: 1,
+            },
+            ArrayDimension {
+                len: i32::try_from(self.columns)?,
+                lower_bound: 1,
+            },
+        ];
+        protocol::array_to_sql(
+            dimensions,
+            member.oid(),
+            &self.data,
+            |value, out| match value.to_sql(member, out)? {
+                IsNull::Yes => Ok(postgres_protocol::IsNull::Yes),
+                IsNull::No => Ok(postgres_protocol::IsNull::No),
+            },
+            out,
+        )?;
+
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        match ty.kind() {
+            Kind::Array(member) => T::accepts(member),
+            _ => false,
+        }
+    }
+
+    to_sql_checked!();
+}
+
+/// A Postgres range, e.g. `INT4RANGE`, `INT8RANGE`, `NUMRANGE`, `DATERANGE`, `TSRANGE` or
+/// `TSTZRANGE`.
+///
+/// # Example
+///
+/// ```
+/// # use std::ops::Bound;
+/// # use sqlm_postgres::{sql, types::Range};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let range: Range<i32> = sql!("SELECT '[1,10)'::INT4RANGE").await?;
+/// assert_eq!(range, Range::new(Bound::Included(1), Bound::Excluded(10)));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Range<T> {
+    /// An empty range (e.g. `'empty'::INT4RANGE`).
+    #[default]
+    Empty,
+    /// A non-empty range; each bound is either inclusive, exclusive or unbounded.
+    Nonempty { lower: Bound<T>, upper: Bound<T> },
+}
+
+impl<T> Range<T> {
+    /// Create a new non-empty range.
+    pub fn new(lower: Bound<T>, upper: Bound<T>) -> Self {
+        Self::Nonempty { lower, upper }
+    }
+
+    /// Create a new empty range.
+    pub fn empty() -> Self {
+        Self::Empty
+    }
+
+    /// Whether this is an empty range.
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Self::Empty)
+    }
+
+    /// The lower bound of the range, or [`None`] if the range is empty.
+    pub fn lower(&self) -> Option<Bound<&T>> {
+        match self {
+            Self::Empty => None,
+            Self::Nonempty { lower, .. } => Some(lower.as_ref()),
+        }
+    }
+
+    /// The upper bound of the range, or [`None`] if the range is empty.
+    pub fn upper(&self) -> Option<Bound<&T>> {
+        match self {
+            Self::Empty => None,
+            Self::Nonempty { upper, .. } => Some(upper.as_ref()),
+        }
+    }
+}
+
+impl<'a, T> FromSql<'a> for Range<T>
+where
+    T: FromSql<'a>,
+{
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn error::Error + Sync + Send>> {
+        let Kind::Range(element) = ty.kind() else {
+            return Err(format!("expected a range type, got {ty}").into());
+        };
+
+        match protocol::range_from_sql(raw)? {
+            protocol::Range::Empty => Ok(Self::Empty),
+            protocol::Range::Nonempty(lower, upper) => Ok(Self::Nonempty {
+                lower: bound_from_sql(lower, element)?,
+                upper: bound_from_sql(upper, element)?,
+            }),
+        }
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        match ty.kind() {
+            Kind::Range(element) => T::accepts(element),
+            _ => false,
+        }
+    }
+}
+
+fn bound_from_sql<'a, T>(
+    bound: RangeBound<Option<&'a [u8]>>,
+    ty: &Type,
+) -> Result<Bound<T>, Box<dyn error::Error + Sync + Send>>
+where
+    T: FromSql<'a>,
+{
+    Ok(match bound {
+        RangeBound::Inclusive(raw) => Bound::Included(T::from_sql_nullable(ty, raw)?),
+        RangeBound::Exclusive(raw) => Bound::Excluded(T::from_sql_nullable(ty, raw)?),
+        RangeBound::Unbounded => Bound::Unbounded,
+    })
+}
+
+impl<T> ToSql for Range<T>
+where
+    T: ToSql,
+{
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn error::Error + Sync + Send>> {
+        let Kind::Range(element) = ty.kind() else {
+            return Err(format!("expected a range type, got {ty}").into());
+        };
+
+        match self {
+            Self::Empty => protocol::empty_range_to_sql(out),
+            Self::Nonempty { lower, upper } => protocol::range_to_sql(
+                |out| bound_to_sql(lower, element, out),
+                |out| bound_to_sql(upper, element, out),
+                out,
+            )?,
+        }
+
+        Ok(IsNull::No)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        match ty.kind() {
+            Kind::Range(element) => T::accepts(element),
+            _ => false,
+        }
+    }
+
+    to_sql_checked!();
+}
+
+fn bound_to_sql<T>(
+    bound: &Bound<T>,
+    ty: &Type,
+    out: &mut BytesMut,
+) -> Result<RangeBound<postgres_protocol::IsNull>, Box<dyn error::Error + Sync + Send>>
+where
+    T: ToSql,
+{
+    let is_null = |is_null| match is_null {
+        IsNull::Yes => postgres_protocol::IsNull::Yes,
+        IsNull::No => postgres_protocol::IsNull::No,
+    };
+    Ok(match bound {
+        Bound::Included(value) => RangeBound::Inclusive(is_null(value.to_sql(ty, out)?)),
+        Bound::Excluded(value) => RangeBound::Exclusive(is_null(value.to_sql(ty, out)?)),
+        Bound::Unbounded => RangeBound::Unbounded,
+    })
+}
+
+/// A JSON value (`JSON` or `JSONB`) deserialized into (and serialized from) `T` via serde.
+///
+/// `Json<T>` is compile-time checked like a [`serde_json::Value`]. For fields of a
+/// [`FromRow`](crate::FromRow) struct, `#[sqlm(json)]` can be used instead of wrapping the field's
+/// type.
+///
+/// # Example
+///
+/// ```
+/// # use sqlm_postgres::{sql, types::Json};
+/// #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+/// struct Settings {
+///     theme: String,
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let Json(settings): Json<Settings> = sql!("SELECT '{{\"theme\": \"dark\"}}'::JSONB").await?;
+/// assert_eq!(settings.theme, "dark");
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "json")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Json<T>(pub T);
+
+#[cfg(feature = "json")]
+impl<'a, T> FromSql<'a> for Json<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    fn from_sql(ty: &Type, raw: &'a [u8]) -> Result<Self, Box<dyn error::Error + Sync + Send>> {
+        tokio_postgres::types::Json::<T>::from_sql(ty, raw).map(|json| Self(json.0))
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <serde_json::Value as FromSql>::accepts(ty)
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T> ToSql for Json<T>
+where
+    T: serde::Serialize + std::fmt::Debug,
+{
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn error::Error + Sync + Send>> {
+        tokio_postgres::types::Json(&self.0).to_sql(ty, out)
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <serde_json::Value as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
 macro_rules! impl_type {
     ($ty:path) => {
         impl SqlType for $ty {
@@ -156,17 +644,58 @@ impl_type!(f32);
 impl_type!(f64);
 impl_type!(bool);
 impl_type!(String);
+impl_type!(std::net::IpAddr);
+impl_type!(std::collections::HashMap<String, Option<String>>);
 #[cfg(feature = "json")]
 impl_type!(serde_json::Value);
 #[cfg(feature = "time")]
 impl_type!(time::OffsetDateTime);
 #[cfg(feature = "time")]
+impl_type!(time::PrimitiveDateTime);
+#[cfg(feature = "time")]
 impl_type!(time::Date);
 #[cfg(feature = "uuid")]
 impl_type!(uuid::Uuid);
 #[cfg(feature = "pgvector")]
 impl_type!(pgvector::Vector);
+#[cfg(feature = "cidr")]
+impl_type!(cidr::IpCidr);
+#[cfg(feature = "eui48")]
+impl_type!(eui48::MacAddress);
+#[cfg(feature = "decimal")]
+impl_type!(rust_decimal::Decimal);
 
 impl SqlType for &str {
     type Type = String;
 }
+
+impl<T> SqlType for Array2<T>
+where
+    T: SqlType,
+{
+    type Type = Vec<T::Type>;
+}
+
+impl<T> SqlType for Array2<Option<T>>
+where
+    T: SqlType,
+{
+    type Type = Vec<T::Type>;
+}
+
+#[cfg(feature = "json")]
+impl<T> SqlType for Json<T> {
+    type Type = serde_json::Value;
+}
+
+#[cfg(feature = "json")]
+impl<T> SqlType for &Json<T> {
+    type Type = serde_json::Value;
+}
+
+impl<T> SqlType for Range<T>
+where
+    T: SqlType,
+{
+    type Type = Range<T::Type>;
+}
@@ -1,4 +1,16 @@
+CREATE EXTENSION IF NOT EXISTS citext;
+CREATE EXTENSION IF NOT EXISTS ltree;
+CREATE EXTENSION IF NOT EXISTS hstore;
+
+CREATE DOMAIN email AS TEXT CHECK (VALUE LIKE '%@%');
+CREATE DOMAIN score AS JSONB;
+
 CREATE TYPE role AS ENUM ('user', 'admin');
+CREATE TYPE order_status AS ENUM ('active', 'inactive');
+CREATE TYPE payment_status AS ENUM ('active', 'inactive');
+
+CREATE SCHEMA billing;
+CREATE TYPE billing.payment_status AS ENUM ('active', 'inactive');
 
 CREATE TABLE users (
     id BIGSERIAL PRIMARY KEY,
@@ -8,3 +20,14 @@ CREATE TABLE users (
 
 INSERT INTO users VALUES (DEFAULT, 'first', 'admin');
 INSERT INTO users VALUES (DEFAULT, NULL, 'user');
+
+CREATE TABLE accounts (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL REFERENCES users (id),
+    email TEXT NOT NULL UNIQUE,
+    balance BIGINT NOT NULL DEFAULT 0 CHECK (balance >= 0),
+    active INT4RANGE NULL,
+    EXCLUDE USING gist (active WITH &&)
+);
+
+INSERT INTO accounts (user_id, email, active) VALUES (1, 'first@example.com', '[1,10)');
@@ -0,0 +1,135 @@
+use postgres_types::{FromSql, ToSql};
+use sqlm_postgres::types::Array2;
+use sqlm_postgres::{Enum, FromRow, sql};
+
+#[derive(Debug, Default, FromSql, ToSql, Enum, PartialEq, Eq)]
+#[postgres(name = "role")]
+enum Role {
+    #[default]
+    #[postgres(name = "user")]
+    User,
+    #[postgres(name = "admin")]
+    Admin,
+}
+
+#[tokio::test]
+async fn test_nullable_elements() {
+    let val: Vec<Option<i64>> = sql!("SELECT ARRAY[1, NULL, 3]::BIGINT[]").await.unwrap();
+    assert_eq!(val, vec![Some(1), None, Some(3)]);
+}
+
+#[tokio::test]
+async fn test_nullable_elements_param() {
+    let expected = vec![Some("foo".to_string()), None];
+    let val: Vec<Option<String>> = sql!("SELECT {expected}::TEXT[]").await.unwrap();
+    assert_eq!(val, expected);
+}
+
+#[tokio::test]
+async fn test_nullable_elements_option_param() {
+    let ids = Some(vec![Some(1i64), None]);
+    let id: i64 = sql!("SELECT id FROM users WHERE id = ANY({ids})")
+        .await
+        .unwrap();
+    assert_eq!(id, 1);
+}
+
+#[tokio::test]
+async fn test_nullable_rows() {
+    let names: Vec<Option<String>> = sql!("SELECT name FROM users ORDER BY id").await.unwrap();
+    assert_eq!(names, vec![Some("first".to_string()), None]);
+}
+
+#[tokio::test]
+async fn test_nullable_enum_elements() {
+    let roles: Vec<Option<Role>> = sql!("SELECT ARRAY['admin', NULL]::role[]").await.unwrap();
+    assert_eq!(roles, vec![Some(Role::Admin), None]);
+
+    let roles: Vec<Option<Role>> = sql!("SELECT {roles}::role[]").await.unwrap();
+    assert_eq!(roles, vec![Some(Role::Admin), None]);
+}
+
+#[tokio::test]
+async fn test_nullable_elements_property() {
+    #[derive(Debug, PartialEq, Eq, FromRow)]
+    struct Row {
+        id: i64,
+        values: Vec<Option<i32>>,
+    }
+
+    let row: Row = sql!("SELECT 1::BIGINT AS id, ARRAY[NULL, 2]::INT[] AS values")
+        .await
+        .unwrap();
+    assert_eq!(
+        row,
+        Row {
+            id: 1,
+            values: vec![None, Some(2)]
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_array2() {
+    let matrix: Array2<i32> = sql!("SELECT '{{{{1,2,3}},{{4,5,6}}}}'::INT[]")
+        .await
+        .unwrap();
+    assert_eq!(matrix.dimensions(), (2, 3));
+    assert_eq!(
+        matrix.rows().collect::<Vec<_>>(),
+        vec![&[1, 2, 3][..], &[4, 5, 6][..]]
+    );
+    assert_eq!(matrix.get(1, 2), Some(&6));
+    assert_eq!(matrix.get(2, 0), None);
+}
+
+#[tokio::test]
+async fn test_array2_empty() {
+    let matrix: Array2<i32> = sql!("SELECT '{{}}'::INT[]").await.unwrap();
+    assert_eq!(matrix.dimensions(), (0, 0));
+}
+
+#[tokio::test]
+async fn test_array2_param() {
+    let expected = Array2::from_vec(2, 2, vec![1i64, 2, 3, 4]).unwrap();
+    let matrix: Array2<i64> = sql!("SELECT {expected}::BIGINT[]").await.unwrap();
+    assert_eq!(matrix, expected);
+
+    let dims: String = sql!("SELECT array_dims({expected}::BIGINT[])")
+        .await
+        .unwrap();
+    assert_eq!(dims, "[1:2][1:2]");
+}
+
+#[tokio::test]
+async fn test_array2_nullable_elements() {
+    let matrix: Array2<Option<f64>> = sql!("SELECT '{{{{1.5,NULL}},{{NULL,2.5}}}}'::FLOAT8[]")
+        .await
+        .unwrap();
+    assert_eq!(matrix.as_slice(), &[Some(1.5), None, None, Some(2.5)]);
+}
+
+#[tokio::test]
+async fn test_array2_property() {
+    #[derive(Debug, PartialEq, Eq, FromRow)]
+    struct Feature {
+        id: i64,
+        weights: Array2<i32>,
+    }
+
+    let feature: Feature = sql!("SELECT 1::BIGINT AS id, '{{{{1}},{{2}}}}'::INT[] AS weights")
+        .await
+        .unwrap();
+    assert_eq!(
+        feature,
+        Feature {
+            id: 1,
+            weights: Array2::from_vec(2, 1, vec![1, 2]).unwrap(),
+        }
+    );
+}
+
+#[test]
+fn test_array2_from_vec_mismatch() {
+    assert_eq!(Array2::from_vec(2, 2, vec![1, 2, 3]), None);
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "axum")]
+
+use axum_core::response::IntoResponse;
+use http_body_util::BodyExt;
+use sqlm_postgres::error::{StatusCode, register_constraint};
+use sqlm_postgres::sql;
+
+#[tokio::test]
+async fn test_into_response() {
+    let err = sql!("INSERT INTO users (id) VALUES (1)").await.unwrap_err();
+    let res = err.into_response();
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, "Conflict");
+}
+
+#[tokio::test]
+async fn test_into_response_registered_constraint() {
+    register_constraint(
+        "accounts_email_key",
+        StatusCode::CONFLICT,
+        Some("email taken"),
+    );
+
+    let err = sql!("INSERT INTO accounts (user_id, email) VALUES (1, 'first@example.com')")
+        .await
+        .unwrap_err();
+    let res = err.into_response();
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+    let body = res.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(body, "email taken");
+}
@@ -0,0 +1,127 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use sqlm_postgres::{Enum, FromRow, FromSql, ToSql, sql};
+
+#[derive(Debug, Default, Clone, Copy, FromSql, ToSql, Enum, PartialEq, Eq, Hash)]
+#[postgres(name = "role")]
+enum Role {
+    #[default]
+    #[postgres(name = "user")]
+    User,
+    #[postgres(name = "admin")]
+    Admin,
+}
+
+#[derive(Debug, PartialEq, Eq, FromRow)]
+struct User {
+    #[sqlm(key)]
+    id: i64,
+    name: Option<String>,
+    role: Role,
+}
+
+#[derive(Debug, PartialEq, Eq, FromRow)]
+struct UserName {
+    #[sqlm(key)]
+    id: i64,
+    name: Option<String>,
+}
+
+#[tokio::test]
+async fn test_collect_map() {
+    let names: HashMap<i64, Option<String>> = sql!("SELECT id, name FROM users").await.unwrap();
+    assert_eq!(
+        names,
+        HashMap::from([(1, Some("first".to_string())), (2, None)])
+    );
+
+    // the first column is the key, regardless of the column names
+    let ids: BTreeMap<String, i64> = sql!("SELECT name, id FROM users WHERE name IS NOT NULL")
+        .await
+        .unwrap();
+    assert_eq!(ids, BTreeMap::from([("first".to_string(), 1)]));
+
+    let roles: HashMap<i64, Role> = sql!("SELECT id, role FROM users").await.unwrap();
+    assert_eq!(roles, HashMap::from([(1, Role::Admin), (2, Role::User)]));
+}
+
+#[tokio::test]
+async fn test_collect_set() {
+    let ids: HashSet<i64> = sql!("SELECT id FROM users").await.unwrap();
+    assert_eq!(ids, HashSet::from([1, 2]));
+
+    let roles: BTreeSet<String> = sql!("SELECT role::TEXT FROM users UNION ALL SELECT 'user'")
+        .await
+        .unwrap();
+    assert_eq!(
+        roles,
+        BTreeSet::from(["admin".to_string(), "user".to_string()])
+    );
+}
+
+#[tokio::test]
+async fn test_collect_keyed() {
+    let users: HashMap<i64, User> = sql!("SELECT id, name, role FROM users").await.unwrap();
+    assert_eq!(
+        users,
+        HashMap::from([
+            (
+                1,
+                User {
+                    id: 1,
+                    name: Some("first".to_string()),
+                    role: Role::Admin,
+                }
+            ),
+            (
+                2,
+                User {
+                    id: 2,
+                    name: None,
+                    role: Role::User,
+                }
+            ),
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_collect_keyed_two_columns() {
+    let names: BTreeMap<i64, UserName> = sql!("SELECT id, name FROM users").await.unwrap();
+    assert_eq!(
+        names,
+        BTreeMap::from([
+            (
+                1,
+                UserName {
+                    id: 1,
+                    name: Some("first".to_string()),
+                }
+            ),
+            (2, UserName { id: 2, name: None }),
+        ])
+    );
+
+    // still readable as a struct
+    let name: UserName = sql!("SELECT id, name FROM users WHERE id = 1")
+        .await
+        .unwrap();
+    assert_eq!(name.name.as_deref(), Some("first"));
+}
+
+#[cfg(feature = "indexmap")]
+#[tokio::test]
+async fn test_collect_index() {
+    use indexmap::{IndexMap, IndexSet};
+
+    let ids: IndexSet<i64> = sql!("SELECT id FROM users ORDER BY id DESC").await.unwrap();
+    assert_eq!(ids.into_iter().collect::<Vec<_>>(), vec![2, 1]);
+
+    let roles: IndexMap<i64, Role> = sql!("SELECT id, role FROM users ORDER BY id DESC")
+        .await
+        .unwrap();
+    assert_eq!(
+        roles.into_iter().collect::<Vec<_>>(),
+        vec![(2, Role::User), (1, Role::Admin)]
+    );
+}
@@ -0,0 +1,120 @@
+use sqlm_postgres::{FromRow, sql};
+
+#[derive(Debug, PartialEq, Eq, FromRow)]
+struct User {
+    id: i64,
+    name: Option<String>,
+}
+
+mod models {
+    #[derive(Debug, PartialEq, Eq, sqlm_postgres::FromRow)]
+    pub struct Account {
+        pub id: i64,
+        pub email: String,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, FromRow)]
+struct UserWithEmail {
+    id: i64,
+    name: Option<String>,
+    email: String,
+}
+
+#[tokio::test]
+async fn test_columns() {
+    let user: User = sql!("SELECT {User::*} FROM users WHERE id = 1")
+        .await
+        .unwrap();
+    assert_eq!(
+        user,
+        User {
+            id: 1,
+            name: Some("first".to_string())
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_columns_alias() {
+    let user_id = 1i64;
+    let accounts: Vec<models::Account> = sql!(
+        "SELECT {a.models::Account::*}
+        FROM accounts a JOIN users u ON u.id = a.user_id
+        WHERE u.id = {user_id}"
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        accounts,
+        vec![models::Account {
+            id: 1,
+            email: "first@example.com".to_string(),
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_columns_local_struct() {
+    #[derive(Debug, PartialEq, Eq, FromRow)]
+    struct Name {
+        id: i64,
+        name: Option<String>,
+    }
+
+    let names: Vec<Name> = sql!("SELECT {Name::*} FROM users ORDER BY id")
+        .await
+        .unwrap();
+    assert_eq!(names.len(), 2);
+}
+
+#[tokio::test]
+async fn test_columns_combined() {
+    let user: UserWithEmail = sql!(
+        "SELECT {u.User::*}, a.email FROM users u JOIN accounts a ON a.user_id = u.id WHERE u.id = 1"
+    )
+    .await
+    .unwrap();
+    assert_eq!(user.email, "first@example.com");
+}
+
+mod shadowed {
+    // The derive must not add `Enum` to the macro namespace, which already has the `Enum` derive.
+    #[allow(unused_imports)]
+    use sqlm_postgres::{Enum, FromRow, sql};
+
+    #[derive(Debug, PartialEq, Eq, FromRow)]
+    pub struct Enum {
+        pub id: i64,
+        pub name: Option<String>,
+    }
+
+    #[tokio::test]
+    async fn test_columns_struct_named_like_macro() {
+        let users: Vec<Enum> = sql!("SELECT {Enum::*} FROM users ORDER BY id")
+            .await
+            .unwrap();
+        assert_eq!(users.len(), 2);
+    }
+}
+
+#[tokio::test]
+async fn test_columns_raw_identifier() {
+    #[derive(Debug, PartialEq, Eq, FromRow)]
+    struct Keyword {
+        id: i64,
+        r#type: Option<String>,
+    }
+
+    let keyword: Keyword =
+        sql!("SELECT {Keyword::*} FROM (SELECT id, name AS type FROM users) t WHERE id = 1")
+            .await
+            .unwrap();
+    assert_eq!(
+        keyword,
+        Keyword {
+            id: 1,
+            r#type: Some("first".to_string())
+        }
+    );
+}
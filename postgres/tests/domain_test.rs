@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use sqlm_postgres::{FromRow, sql};
+
+#[derive(Debug, PartialEq, Eq, FromRow)]
+struct Contact {
+    email: String,
+    emails: Vec<String>,
+}
+
+#[tokio::test]
+async fn test_domain() {
+    let email: String = sql!("SELECT 'first@example.com'::email").await.unwrap();
+    assert_eq!(email, "first@example.com");
+
+    let contact: Contact = sql!(
+        "SELECT 'first@example.com'::email AS email, ARRAY['a@example.com']::email[] AS emails"
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        contact,
+        Contact {
+            email: "first@example.com".to_string(),
+            emails: vec!["a@example.com".to_string()],
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_domain_param() {
+    let email = "first@example.com";
+    let is_valid: bool = sql!("SELECT {email}::email LIKE '%@example.com'")
+        .await
+        .unwrap();
+    assert!(is_valid);
+
+    let emails = vec!["a@example.com".to_string(), "b@example.com".to_string()];
+    let count: i64 = sql!("SELECT cardinality({emails}::email[])::BIGINT")
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+
+    let email = "no email";
+    let err = sql!("SELECT {email}::email::TEXT")
+        .await
+        .map(|_: String| ())
+        .unwrap_err();
+    assert_eq!(err.code().map(|code| code.code()), Some("23514"));
+}
+
+#[tokio::test]
+async fn test_domain_json() {
+    let score = serde_json::json!({ "points": 3 });
+    let points: i64 = sql!("SELECT ({score}::score->>'points')::BIGINT")
+        .await
+        .unwrap();
+    assert_eq!(points, 3);
+
+    let score: serde_json::Value = sql!("SELECT '{{\"points\": 4}}'::score").await.unwrap();
+    assert_eq!(score, serde_json::json!({ "points": 4 }));
+}
+
+#[tokio::test]
+async fn test_citext_ltree() {
+    let name = "First";
+    let is_equal: bool = sql!("SELECT {name}::citext = 'first'::citext")
+        .await
+        .unwrap();
+    assert!(is_equal);
+
+    let path: String = sql!("SELECT 'top.science.astronomy'::ltree").await.unwrap();
+    assert_eq!(path, "top.science.astronomy");
+}
+
+#[tokio::test]
+async fn test_hstore() {
+    let attributes: HashMap<String, Option<String>> =
+        sql!("SELECT 'color => red, size => NULL'::hstore")
+            .await
+            .unwrap();
+    assert_eq!(
+        attributes,
+        HashMap::from([
+            ("color".to_string(), Some("red".to_string())),
+            ("size".to_string(), None),
+        ])
+    );
+
+    let attributes = HashMap::from([("color".to_string(), Some("blue".to_string()))]);
+    let color: String = sql!("SELECT {attributes}::hstore -> 'color'")
+        .await
+        .unwrap();
+    assert_eq!(color, "blue");
+}
@@ -0,0 +1,168 @@
+use sqlm_postgres::error::{ConstraintViolation, ErrorKind};
+use sqlm_postgres::{FromRow, PgEnum, connect, sql};
+
+#[derive(Debug, Default, PartialEq, Eq, PgEnum)]
+#[sqlm(text, rename_all = "snake_case")]
+enum Visibility {
+    #[default]
+    Private,
+    Public,
+}
+
+#[tokio::test]
+async fn test_unique_violation() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+    let err = sql!("INSERT INTO accounts (user_id, email) VALUES (2, 'first@example.com')")
+        .run_with(&tx)
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.constraint_violation(),
+        Some(ConstraintViolation::Unique)
+    );
+    assert!(err.is_duplicate_key());
+    assert_eq!(err.constraint(), Some("accounts_email_key"));
+
+    let db_error = err.db_error().unwrap();
+    assert_eq!(db_error.table(), Some("accounts"));
+    assert_eq!(
+        db_error.detail(),
+        Some("Key (email)=(first@example.com) already exists.")
+    );
+}
+
+#[tokio::test]
+async fn test_foreign_key_violation() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+    let err = sql!("INSERT INTO accounts (user_id, email) VALUES (-1, 'fk@example.com')")
+        .run_with(&tx)
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.constraint_violation(),
+        Some(ConstraintViolation::ForeignKey)
+    );
+    assert!(err.is_foreign_key());
+    assert_eq!(err.constraint(), Some("accounts_user_id_fkey"));
+}
+
+#[tokio::test]
+async fn test_check_violation() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+    let err =
+        sql!("INSERT INTO accounts (user_id, email, balance) VALUES (1, 'check@example.com', -1)")
+            .run_with(&tx)
+            .await
+            .unwrap_err();
+
+    assert_eq!(err.constraint_violation(), Some(ConstraintViolation::Check));
+    assert_eq!(err.constraint(), Some("accounts_balance_check"));
+}
+
+#[tokio::test]
+async fn test_not_null_violation() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+    let err = sql!("INSERT INTO accounts (user_id, email) VALUES (1, NULL)")
+        .run_with(&tx)
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        err.constraint_violation(),
+        Some(ConstraintViolation::NotNull)
+    );
+    let db_error = err.db_error().unwrap();
+    assert_eq!(db_error.table(), Some("accounts"));
+    assert_eq!(db_error.column(), Some("email"));
+}
+
+#[tokio::test]
+async fn test_exclusion_violation() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+    let err = sql!(
+        "INSERT INTO accounts (user_id, email, active) VALUES (1, 'excl@example.com', '[5,15)')"
+    )
+    .run_with(&tx)
+    .await
+    .unwrap_err();
+
+    assert_eq!(
+        err.constraint_violation(),
+        Some(ConstraintViolation::Exclusion)
+    );
+    assert_eq!(err.constraint(), Some("accounts_active_excl"));
+}
+
+#[tokio::test]
+async fn test_no_constraint_violation() {
+    let err = sql!("SELECT name FROM users WHERE id = -1")
+        .await
+        .map(|_: String| ())
+        .unwrap_err();
+    assert!(err.is_row_not_found());
+    assert_eq!(err.constraint_violation(), None);
+    assert!(err.db_error().is_none());
+}
+
+#[tokio::test]
+async fn test_kind_decode() {
+    let err = sql!("SELECT 'unknown'::TEXT AS visibility")
+        .await
+        .map(|_: Visibility| ())
+        .unwrap_err();
+    let ErrorKind::Decode { column, .. } = err.kind() else {
+        panic!("expected decode error, got {err:?}");
+    };
+    assert_eq!(column, "visibility");
+
+    #[derive(Debug, FromRow)]
+    #[allow(dead_code)]
+    struct Post {
+        id: i64,
+        visibility: Visibility,
+    }
+    let err = sql!("SELECT 1::BIGINT AS id, 'unknown'::TEXT AS visibility")
+        .await
+        .map(|_: Post| ())
+        .unwrap_err();
+    let ErrorKind::Decode { column, .. } = err.kind() else {
+        panic!("expected decode error, got {err:?}");
+    };
+    assert_eq!(column, "visibility");
+    assert_eq!(err.to_string(), "failed to read column `visibility`");
+}
+
+#[tokio::test]
+async fn test_kind_timeout() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+    sql!("SET LOCAL statement_timeout = 10")
+        .run_with(&tx)
+        .await
+        .unwrap();
+    let err = sql!("SELECT pg_sleep(1)::TEXT")
+        .run_with(&tx)
+        .await
+        .map(|_: Option<String>| ())
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Timeout(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn test_kind_row_not_found() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+    let err = sql!("SELECT id FROM users WHERE id = -1")
+        .run_with(&tx)
+        .await
+        .map(|_: i64| ())
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::RowNotFound), "{err:?}");
+}
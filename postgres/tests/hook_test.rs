@@ -0,0 +1,156 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use sqlm_postgres::error::ErrorKind;
+use sqlm_postgres::hook::{Outcome, QueryHook, QueryInfo};
+use sqlm_postgres::pool::Pool;
+use sqlm_postgres::{Error, sql};
+
+#[derive(Debug, PartialEq, Eq)]
+enum Call {
+    Before(String, String),
+    After(String, Outcome),
+    Error(String, Option<String>),
+}
+
+#[derive(Default, Clone)]
+struct Recorder(Arc<Mutex<Vec<Call>>>);
+
+impl QueryHook for Recorder {
+    fn before(&self, info: &QueryInfo<'_>) -> Result<(), Error> {
+        self.0.lock().unwrap().push(Call::Before(
+            info.query().to_string(),
+            format!("{:?}", info.parameters()),
+        ));
+        Ok(())
+    }
+
+    fn after(&self, info: &QueryInfo<'_>, _elapsed: Duration, outcome: Outcome) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(Call::After(info.query().to_string(), outcome));
+    }
+
+    fn error(&self, info: &QueryInfo<'_>, _elapsed: Duration, err: &Error) {
+        self.0.lock().unwrap().push(Call::Error(
+            info.query().to_string(),
+            err.code().map(|code| code.code().to_string()),
+        ));
+    }
+}
+
+struct ReadOnly;
+
+impl QueryHook for ReadOnly {
+    fn before(&self, info: &QueryInfo<'_>) -> Result<(), Error> {
+        if info.query().starts_with("SELECT") {
+            Ok(())
+        } else {
+            Err(ErrorKind::Hook("read-only".into()).into())
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_hook_callbacks() {
+    let recorder = Recorder::default();
+    let pool = Pool::from_env(2).unwrap().with_hook(recorder.clone());
+    let mut conn = pool.connect().await.unwrap();
+
+    let id = 1i64;
+    let name: String = sql!("SELECT name FROM users WHERE id = {id}")
+        .run_with(&conn)
+        .await
+        .unwrap();
+    assert_eq!(name, "first");
+
+    let ids: Vec<i64> = sql!("SELECT id FROM users ORDER BY id")
+        .run_with(&conn)
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![1, 2]);
+
+    let tx = conn.transaction().await.unwrap();
+    sql!("UPDATE users SET name = name")
+        .run_with(&tx)
+        .await
+        .unwrap();
+    tx.rollback().await.unwrap();
+
+    let id = "no number";
+    sql!("SELECT {id}::TEXT::BIGINT AS id")
+        .run_with(&conn)
+        .await
+        .map(|_: i64| ())
+        .unwrap_err();
+
+    assert_eq!(
+        *recorder.0.lock().unwrap(),
+        vec![
+            Call::Before(
+                "SELECT name FROM users WHERE id = $1".to_string(),
+                "[1]".to_string()
+            ),
+            Call::After(
+                "SELECT name FROM users WHERE id = $1".to_string(),
+                Outcome::Rows(1)
+            ),
+            Call::Before(
+                "SELECT id FROM users ORDER BY id".to_string(),
+                "[]".to_string()
+            ),
+            Call::After(
+                "SELECT id FROM users ORDER BY id".to_string(),
+                Outcome::Rows(2)
+            ),
+            Call::Before("UPDATE users SET name = name".to_string(), "[]".to_string()),
+            Call::After(
+                "UPDATE users SET name = name".to_string(),
+                Outcome::Affected(2)
+            ),
+            Call::Before(
+                "SELECT $1::TEXT::BIGINT AS id".to_string(),
+                r#"["no number"]"#.to_string()
+            ),
+            Call::Error(
+                "SELECT $1::TEXT::BIGINT AS id".to_string(),
+                Some("22P02".to_string())
+            ),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_hook_rejects_query() {
+    let pool = Pool::from_env(2).unwrap().with_hook(ReadOnly);
+    let conn = pool.connect().await.unwrap();
+
+    let ids: Vec<i64> = sql!("SELECT id FROM users ORDER BY id")
+        .run_with(&conn)
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![1, 2]);
+
+    let err = sql!("UPDATE users SET name = name")
+        .run_with(&conn)
+        .await
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Hook(_)), "{err:?}");
+    assert_eq!(
+        std::error::Error::source(&err).unwrap().to_string(),
+        "read-only"
+    );
+}
+
+#[cfg(feature = "global_pool")]
+#[tokio::test]
+async fn test_global_pool_hook() {
+    let recorder = Recorder::default();
+    let pool = Pool::from_env(2).unwrap().with_hook(recorder.clone());
+    assert!(sqlm_postgres::set_pool(pool).is_ok());
+
+    let ids: Vec<i64> = sql!("SELECT id FROM users ORDER BY id").await.unwrap();
+    assert_eq!(ids, vec![1, 2]);
+    assert_eq!(recorder.0.lock().unwrap().len(), 2);
+}
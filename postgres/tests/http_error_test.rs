@@ -0,0 +1,62 @@
+#![cfg(feature = "http-error")]
+
+use std::fmt;
+
+use http_error::{HttpError, StatusCode};
+use sqlm_postgres::error::register_constraint;
+use sqlm_postgres::{Error, connect, sql};
+
+struct Reason<'a>(&'a Error);
+
+impl fmt::Display for Reason<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.reason(f)
+    }
+}
+
+#[tokio::test]
+async fn test_status_code() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+    let err = sql!("SELECT id FROM users WHERE id = -1")
+        .run_with(&tx)
+        .await
+        .map(|_: i64| ())
+        .unwrap_err();
+    assert!(err.is_row_not_found());
+    assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+    assert_eq!(Reason(&err).to_string(), "Not Found");
+
+    let err = sql!("INSERT INTO users (id) VALUES (1)").await.unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::CONFLICT);
+
+    let err = sql!("INSERT INTO accounts (user_id, email) VALUES (1, NULL)")
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let id = "foo";
+    let err = sql!("SELECT {id}::TEXT::BIGINT")
+        .await
+        .map(|_: i64| ())
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let err = sql!("SELECT 1 / 0").await.map(|_: i32| ()).unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn test_registered_constraint() {
+    register_constraint(
+        "accounts_user_id_fkey",
+        StatusCode::NOT_FOUND,
+        Some("user not found"),
+    );
+
+    let err = sql!("INSERT INTO accounts (user_id, email) VALUES (-1, 'registered@example.com')")
+        .await
+        .unwrap_err();
+    assert_eq!(err.status_code(), StatusCode::NOT_FOUND);
+    assert_eq!(Reason(&err).to_string(), "user not found");
+}
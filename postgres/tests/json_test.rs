@@ -0,0 +1,111 @@
+#![cfg(feature = "json")]
+
+use serde::{Deserialize, Serialize};
+use sqlm_postgres::error::ErrorKind;
+use sqlm_postgres::types::Json;
+use sqlm_postgres::{FromRow, sql};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Settings {
+    theme: String,
+    notifications: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, FromRow)]
+struct Profile {
+    id: i64,
+    settings: Json<Settings>,
+}
+
+#[derive(Debug, PartialEq, Eq, FromRow)]
+struct ProfileAttr {
+    id: i64,
+    #[sqlm(json)]
+    settings: Settings,
+    #[sqlm(json)]
+    previous: Option<Settings>,
+}
+
+fn dark() -> Settings {
+    Settings {
+        theme: "dark".to_string(),
+        notifications: true,
+    }
+}
+
+#[tokio::test]
+async fn test_json_literal() {
+    let settings: Json<Settings> =
+        sql!(r#"SELECT '{{"theme": "dark", "notifications": true}}'::JSONB"#)
+            .await
+            .unwrap();
+    assert_eq!(settings, Json(dark()));
+
+    let settings: Option<Json<Settings>> = sql!("SELECT NULL::JSON").await.unwrap();
+    assert_eq!(settings, None);
+
+    let settings: Vec<Json<Settings>> =
+        sql!(r#"SELECT ARRAY['{{"theme": "dark", "notifications": true}}']::JSONB[]"#)
+            .await
+            .unwrap();
+    assert_eq!(settings, vec![Json(dark())]);
+}
+
+#[tokio::test]
+async fn test_json_param() {
+    let settings = Json(dark());
+    let theme: String = sql!("SELECT {settings}::JSONB->>'theme'").await.unwrap();
+    assert_eq!(theme, "dark");
+
+    let Json(roundtrip): Json<Settings> = sql!("SELECT {settings}::JSONB").await.unwrap();
+    assert_eq!(roundtrip, dark());
+}
+
+#[tokio::test]
+async fn test_json_invalid() {
+    let err = sql!(r#"SELECT '{{"theme": 1}}'::JSONB"#)
+        .await
+        .map(|_: Json<Settings>| ())
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::Decode { column, .. } if column == "jsonb"));
+}
+
+#[tokio::test]
+async fn test_json_from_row() {
+    let profile: Profile = sql!(
+        r#"SELECT 1::BIGINT AS id, '{{"theme": "dark", "notifications": true}}'::JSONB AS settings"#
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        profile,
+        Profile {
+            id: 1,
+            settings: Json(dark()),
+        }
+    );
+
+    let profile: ProfileAttr = sql!(
+        r#"SELECT 1::BIGINT AS id, '{{"theme": "dark", "notifications": true}}'::JSONB AS settings,
+            NULL::JSONB AS previous"#
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        profile,
+        ProfileAttr {
+            id: 1,
+            settings: dark(),
+            previous: None,
+        }
+    );
+
+    let profile: ProfileAttr = sql!(
+        r#"SELECT 1::BIGINT AS id, NULL::JSONB AS settings,
+            '{{"theme": "dark", "notifications": true}}'::JSON AS previous"#
+    )
+    .await
+    .unwrap();
+    assert_eq!(profile.settings, Settings::default());
+    assert_eq!(profile.previous, Some(dark()));
+}
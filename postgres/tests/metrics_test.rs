@@ -0,0 +1,54 @@
+#![cfg(feature = "metrics")]
+
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use sqlm_postgres::sql;
+
+#[tokio::test]
+async fn test_metrics() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    recorder.install().unwrap();
+
+    let _: Vec<i64> = sql!("SELECT id FROM users").await.unwrap();
+    let id = "no number";
+    sql!("SELECT {id}::TEXT::BIGINT AS id")
+        .await
+        .map(|_: i64| ())
+        .unwrap_err();
+
+    let metrics = snapshotter.snapshot().into_vec();
+    let find = |name: &str, labels: &[(&str, &str)]| {
+        metrics
+            .iter()
+            .find(|(key, _, _, _)| {
+                key.key().name() == name
+                    && labels.iter().all(|(k, v)| {
+                        key.key()
+                            .labels()
+                            .any(|label| label.key() == *k && label.value() == *v)
+                    })
+            })
+            .map(|(_, _, _, value)| value)
+    };
+
+    let Some(DebugValue::Histogram(durations)) = find(
+        "db.client.operation.duration",
+        &[("db.system", "postgresql"), ("db.operation", "SELECT")],
+    ) else {
+        panic!("missing operation duration histogram");
+    };
+    assert!(!durations.is_empty());
+
+    assert!(matches!(
+        find("db.client.operation.duration", &[("error.type", "22P02")]),
+        Some(DebugValue::Histogram(_))
+    ));
+    assert!(matches!(
+        find("db.client.connection.count", &[("state", "idle")]),
+        Some(DebugValue::Gauge(_))
+    ));
+    assert!(matches!(
+        find("db.client.connection.max", &[]),
+        Some(DebugValue::Gauge(_))
+    ));
+}
@@ -0,0 +1,78 @@
+use sqlm_postgres::{FromRow, Newtype, sql};
+
+#[derive(Debug, Default, PartialEq, Eq, Newtype)]
+struct Id(i64);
+
+#[derive(Debug, Default, PartialEq, Eq, Newtype)]
+struct Name(String);
+
+#[derive(Debug, PartialEq, Eq, FromRow)]
+struct User {
+    id: Id,
+    name: Option<Name>,
+}
+
+#[tokio::test]
+async fn test() {
+    let id: Id = sql!("SELECT id FROM users WHERE id = 1").await.unwrap();
+    assert_eq!(id, Id(1));
+}
+
+#[tokio::test]
+async fn test_option() {
+    let name: Option<Name> = sql!("SELECT name FROM users WHERE id = 1").await.unwrap();
+    assert_eq!(name, Some(Name("first".to_string())));
+    let name: Option<Name> = sql!("SELECT name FROM users WHERE id = 2").await.unwrap();
+    assert_eq!(name, None);
+}
+
+#[tokio::test]
+async fn test_vec() {
+    let ids: Vec<Id> = sql!("SELECT id FROM users ORDER BY id").await.unwrap();
+    assert_eq!(ids, vec![Id(1), Id(2)]);
+}
+
+#[tokio::test]
+async fn test_property() {
+    let users: Vec<User> = sql!("SELECT id, name FROM users ORDER BY id")
+        .await
+        .unwrap();
+    assert_eq!(
+        users,
+        vec![
+            User {
+                id: Id(1),
+                name: Some(Name("first".to_string())),
+            },
+            User {
+                id: Id(2),
+                name: None,
+            }
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_param() {
+    let id = Id(1);
+    let name: Name = sql!("SELECT name FROM users WHERE id = {id}")
+        .await
+        .unwrap();
+    assert_eq!(name, Name("first".to_string()));
+
+    let ids = vec![Id(1), Id(2)];
+    let count: i64 = sql!("SELECT COUNT(*) FROM users WHERE id = ANY({ids})")
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+}
+
+#[tokio::test]
+async fn test_generic() {
+    #[derive(Debug, Default, PartialEq, Eq, Newtype)]
+    struct Wrapper<T>(T);
+
+    let id = Wrapper(2i64);
+    let ids: Vec<Wrapper<i64>> = sql!("SELECT id FROM users WHERE id = {id}").await.unwrap();
+    assert_eq!(ids, vec![Wrapper(2)]);
+}
@@ -62,3 +62,76 @@ async fn test_param_option_vec() {
         .unwrap();
     assert_eq!(id, 1);
 }
+
+struct User {
+    id: i64,
+    name: String,
+}
+
+#[tokio::test]
+async fn test_param_expr() {
+    let user = User {
+        id: 1,
+        name: " first ".to_string(),
+    };
+    let name: String = sql!("SELECT name FROM users WHERE id = {user.id}")
+        .await
+        .unwrap();
+    assert_eq!(name, "first");
+
+    let id: i64 = sql!("SELECT id FROM users WHERE name = {user.name.trim()} AND id = {user.id}")
+        .await
+        .unwrap();
+    assert_eq!(id, 1);
+
+    let ids = [2i64, 1];
+    let pair = (1i64, "first");
+    let id: i64 = sql!("SELECT id FROM users WHERE id = {ids[ids.len() - 1]} AND name = {pair.1}")
+        .await
+        .unwrap();
+    assert_eq!(id, 1);
+}
+
+#[tokio::test]
+async fn test_param_expr_named() {
+    let user = User {
+        id: 2,
+        name: "second".to_string(),
+    };
+    let name: String = sql!(
+        "SELECT name FROM users WHERE id = {user.id} AND name = {user.name.trim()}",
+        user = User {
+            id: 1,
+            name: " first ".to_string(),
+        }
+    )
+    .await
+    .unwrap();
+    assert_eq!(name, "first");
+    assert_eq!(user.id, 2);
+}
+
+#[tokio::test]
+async fn test_param_cast() {
+    let id: Option<i64> = None;
+    let is_null: bool = sql!("SELECT {id::int8} IS NULL").await.unwrap();
+    assert!(is_null);
+
+    let (a, b): (Option<i64>, i64) = (None, 2);
+    let value: i64 = sql!("SELECT COALESCE({a::int8}, {b})").await.unwrap();
+    assert_eq!(value, 2);
+
+    let ids = vec![1i64, 2];
+    let count: i64 = sql!("SELECT cardinality({ids::int8[]})::int8")
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+
+    let ratio = 0.5f64;
+    let ratio: f64 = sql!("SELECT {ratio::double precision}").await.unwrap();
+    assert_eq!(ratio, 0.5);
+
+    let password = "hunter2";
+    let password: String = sql!("SELECT {password::text:secret}").await.unwrap();
+    assert_eq!(password, "hunter2");
+}
@@ -0,0 +1,174 @@
+use std::str::FromStr;
+
+use sqlm_postgres::types::InvalidVariant;
+use sqlm_postgres::{FromRow, PgEnum, sql};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PgEnum)]
+#[sqlm(name = "role", rename_all = "lowercase", as_str, from_str, display)]
+enum Role {
+    #[default]
+    User,
+    Admin,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, PgEnum)]
+#[sqlm(name = "role")]
+enum RenamedRole {
+    #[default]
+    #[sqlm(name = "user")]
+    Member,
+    #[sqlm(name = "admin")]
+    Superuser,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, PgEnum)]
+#[sqlm(text, rename_all = "snake_case")]
+enum Visibility {
+    #[default]
+    Private,
+    PublicRead,
+}
+
+#[derive(Debug, PartialEq, Eq, PgEnum)]
+#[sqlm(name = "payment_status", rename_all = "lowercase")]
+enum PaymentStatus {
+    Active,
+    Inactive,
+}
+
+#[derive(Debug, PartialEq, Eq, PgEnum)]
+#[sqlm(schema = "billing", name = "payment_status", rename_all = "lowercase")]
+enum BillingPaymentStatus {
+    Active,
+    Inactive,
+}
+
+#[derive(Debug, PartialEq, Eq, FromRow)]
+struct User {
+    id: i64,
+    role: Role,
+}
+
+#[tokio::test]
+async fn test_enum() {
+    let role: Role = sql!("SELECT role FROM users WHERE id = 1").await.unwrap();
+    assert_eq!(role, Role::Admin);
+
+    let role: RenamedRole = sql!("SELECT role FROM users WHERE id = 2").await.unwrap();
+    assert_eq!(role, RenamedRole::Member);
+}
+
+#[tokio::test]
+async fn test_enum_option() {
+    let role: Option<Role> = sql!("SELECT role FROM users WHERE id = -1").await.unwrap();
+    assert_eq!(role, None);
+}
+
+#[tokio::test]
+async fn test_enum_vec() {
+    let roles: Vec<Role> = sql!("SELECT ARRAY['admin','user']::role[]").await.unwrap();
+    assert_eq!(roles, vec![Role::Admin, Role::User]);
+}
+
+#[tokio::test]
+async fn test_enum_property_vec() {
+    let users: Vec<User> = sql!("SELECT id, role FROM users ORDER BY id")
+        .await
+        .unwrap();
+    assert_eq!(
+        users,
+        vec![
+            User {
+                id: 1,
+                role: Role::Admin,
+            },
+            User {
+                id: 2,
+                role: Role::User
+            }
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_enum_param() {
+    let role = RenamedRole::Superuser;
+    let id: i64 = sql!("SELECT id FROM users WHERE role = {role}")
+        .await
+        .unwrap();
+    assert_eq!(id, 1);
+
+    let roles = vec![Role::Admin, Role::User];
+    let count: i64 = sql!("SELECT COUNT(*) FROM users WHERE role = ANY({roles})")
+        .await
+        .unwrap();
+    assert_eq!(count, 2);
+}
+
+#[tokio::test]
+async fn test_enum_schema() {
+    let status: PaymentStatus = sql!("SELECT 'active'::payment_status").await.unwrap();
+    assert_eq!(status, PaymentStatus::Active);
+
+    let status: BillingPaymentStatus = sql!("SELECT 'inactive'::billing.payment_status")
+        .await
+        .unwrap();
+    assert_eq!(status, BillingPaymentStatus::Inactive);
+
+    let status = BillingPaymentStatus::Active;
+    let is_active: bool = sql!("SELECT {status} = 'active'::billing.payment_status")
+        .await
+        .unwrap();
+    assert!(is_active);
+}
+
+#[test]
+fn test_as_str() {
+    assert_eq!(Role::Admin.as_str(), "admin");
+    assert_eq!(Role::User.to_string(), "user");
+}
+
+#[test]
+fn test_from_str() {
+    assert_eq!(Role::from_str("admin"), Ok(Role::Admin));
+    let err = Role::from_str("root").unwrap_err();
+    assert_eq!(err, InvalidVariant::new("role", "root"));
+    assert_eq!(err.to_string(), "invalid variant `root` for enum `role`");
+}
+
+#[tokio::test]
+async fn test_text() {
+    let visibility: Visibility = sql!("SELECT 'public_read'::TEXT").await.unwrap();
+    assert_eq!(visibility, Visibility::PublicRead);
+
+    let visibility: Vec<Visibility> = sql!("SELECT ARRAY['private', 'public_read']::VARCHAR[]")
+        .await
+        .unwrap();
+    assert_eq!(
+        visibility,
+        vec![Visibility::Private, Visibility::PublicRead]
+    );
+}
+
+#[tokio::test]
+async fn test_text_param() {
+    let visibility = Visibility::PublicRead;
+    let text: String = sql!("SELECT {visibility}::TEXT").await.unwrap();
+    assert_eq!(text, "public_read");
+}
+
+#[tokio::test]
+async fn test_text_invalid() {
+    let err = sql!("SELECT 'public'::TEXT")
+        .await
+        .map(|_: Visibility| ())
+        .unwrap_err();
+    let mut source: &dyn std::error::Error = &err;
+    while let Some(next) = source.source() {
+        source = next;
+    }
+    assert_eq!(
+        source.to_string(),
+        "invalid variant `public` for enum `Visibility`"
+    );
+}
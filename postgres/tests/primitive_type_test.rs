@@ -445,3 +445,67 @@ mod uuid {
         assert_eq!(val, expected);
     }
 }
+
+mod ip_addr {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_ip_addr() {
+        let expected = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+        let val: IpAddr = sql!("SELECT {expected}::INET").await.unwrap();
+        assert_eq!(val, expected);
+    }
+
+    #[tokio::test]
+    async fn test_ip_addr_option() {
+        let expected = IpAddr::V6(Ipv6Addr::LOCALHOST);
+        let val: Option<IpAddr> = sql!("SELECT {expected}::INET").await.unwrap();
+        assert_eq!(val, Some(expected));
+        let val: Option<IpAddr> = sql!("SELECT NULL::INET").await.unwrap();
+        assert_eq!(val, None);
+    }
+
+    #[tokio::test]
+    async fn test_ip_addr_vec() {
+        let expected = vec![
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            IpAddr::V6(Ipv6Addr::LOCALHOST),
+        ];
+        let val: Vec<IpAddr> = sql!("SELECT {expected}::INET[]").await.unwrap();
+        assert_eq!(val, expected);
+    }
+}
+
+#[cfg(feature = "cidr")]
+mod cidr {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cidr() {
+        let expected = ::cidr::IpCidr::from_str("10.0.0.0/8").unwrap();
+        let val: ::cidr::IpCidr = sql!("SELECT {expected}::CIDR").await.unwrap();
+        assert_eq!(val, expected);
+    }
+
+    #[tokio::test]
+    async fn test_cidr_option() {
+        let val: Option<::cidr::IpCidr> = sql!("SELECT NULL::CIDR").await.unwrap();
+        assert_eq!(val, None);
+    }
+}
+
+#[cfg(feature = "eui48")]
+mod eui48 {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mac_address() {
+        let expected = ::eui48::MacAddress::new([0x08, 0x00, 0x2b, 0x01, 0x02, 0x03]);
+        let val: ::eui48::MacAddress = sql!("SELECT {expected}::MACADDR").await.unwrap();
+        assert_eq!(val, expected);
+    }
+}
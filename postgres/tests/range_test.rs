@@ -0,0 +1,136 @@
+use std::ops::Bound;
+
+use sqlm_postgres::types::Range;
+use sqlm_postgres::{FromRow, sql};
+
+#[tokio::test]
+async fn test_int4range() {
+    let range: Range<i32> = sql!("SELECT '[1,10)'::INT4RANGE").await.unwrap();
+    assert_eq!(range, Range::new(Bound::Included(1), Bound::Excluded(10)));
+}
+
+#[tokio::test]
+async fn test_int8range_unbounded() {
+    let range: Range<i64> = sql!("SELECT '[1,)'::INT8RANGE").await.unwrap();
+    assert_eq!(range, Range::new(Bound::Included(1), Bound::Unbounded));
+}
+
+#[tokio::test]
+async fn test_empty() {
+    let range: Range<i32> = sql!("SELECT 'empty'::INT4RANGE").await.unwrap();
+    assert!(range.is_empty());
+    assert_eq!(range.lower(), None);
+}
+
+#[tokio::test]
+async fn test_option() {
+    let range: Option<Range<i32>> = sql!("SELECT NULL::INT4RANGE").await.unwrap();
+    assert_eq!(range, None);
+}
+
+#[tokio::test]
+async fn test_vec() {
+    let expected = vec![
+        Range::new(Bound::Included(1), Bound::Excluded(2)),
+        Range::empty(),
+    ];
+    let ranges: Vec<Range<i64>> = sql!("SELECT {expected}::INT8RANGE[]").await.unwrap();
+    assert_eq!(ranges, expected);
+}
+
+#[tokio::test]
+async fn test_param() {
+    let range = Range::new(Bound::Included(1i64), Bound::Excluded(2i64));
+    let ids: Vec<i64> = sql!("SELECT id FROM users WHERE {range}::INT8RANGE @> id")
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![1]);
+}
+
+#[tokio::test]
+async fn test_property() {
+    #[derive(Debug, PartialEq, Eq, FromRow)]
+    struct Booking {
+        id: i64,
+        slots: Range<i32>,
+    }
+
+    let booking: Booking = sql!("SELECT 1::BIGINT AS id, '(1,5]'::INT4RANGE AS slots")
+        .await
+        .unwrap();
+    assert_eq!(
+        booking,
+        Booking {
+            id: 1,
+            // Postgres normalizes discrete ranges to `[)`
+            slots: Range::new(Bound::Included(2), Bound::Excluded(6)),
+        }
+    );
+}
+
+#[cfg(feature = "time")]
+mod time {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_tstzrange() {
+        let start = ::time::OffsetDateTime::now_utc()
+            .replace_nanosecond(0)
+            .unwrap();
+        let expected = Range::new(
+            Bound::Included(start),
+            Bound::Excluded(start + ::time::Duration::hours(1)),
+        );
+        let range: Range<::time::OffsetDateTime> =
+            sql!("SELECT {expected}::TSTZRANGE").await.unwrap();
+        assert_eq!(range, expected);
+    }
+
+    #[tokio::test]
+    async fn test_tsrange() {
+        let start = ::time::PrimitiveDateTime::new(
+            ::time::Date::from_calendar_date(2024, ::time::Month::January, 1).unwrap(),
+            ::time::Time::MIDNIGHT,
+        );
+        let expected = Range::new(Bound::Included(start), Bound::Unbounded);
+        let range: Range<::time::PrimitiveDateTime> =
+            sql!("SELECT {expected}::TSRANGE").await.unwrap();
+        assert_eq!(range, expected);
+    }
+
+    #[tokio::test]
+    async fn test_daterange() {
+        let range: Range<::time::Date> = sql!("SELECT '[2024-01-01,2024-01-31]'::DATERANGE")
+            .await
+            .unwrap();
+        assert_eq!(
+            range,
+            Range::new(
+                Bound::Included(
+                    ::time::Date::from_calendar_date(2024, ::time::Month::January, 1).unwrap()
+                ),
+                Bound::Excluded(
+                    ::time::Date::from_calendar_date(2024, ::time::Month::February, 1).unwrap()
+                ),
+            )
+        );
+    }
+}
+
+#[cfg(feature = "decimal")]
+mod decimal {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_numrange() {
+        let range: Range<::rust_decimal::Decimal> =
+            sql!("SELECT '[1.5,2.5)'::NUMRANGE").await.unwrap();
+        assert_eq!(
+            range,
+            Range::new(
+                Bound::Included(::rust_decimal::Decimal::new(15, 1)),
+                Bound::Excluded(::rust_decimal::Decimal::new(25, 1)),
+            )
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use sqlm_postgres::error::ErrorKind;
+use sqlm_postgres::{FromRow, connect, sql};
+
+#[derive(Debug, PartialEq, Eq, FromRow)]
+struct User {
+    id: i64,
+    name: Option<String>,
+}
+
+#[tokio::test]
+async fn test_literal_too_many_rows() {
+    let err = sql!("SELECT id FROM users ORDER BY id")
+        .await
+        .map(|_: i64| ())
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::TooManyRows), "{err:?}");
+
+    let err = sql!("SELECT id FROM users ORDER BY id")
+        .await
+        .map(|_: Option<i64>| ())
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::TooManyRows), "{err:?}");
+}
+
+#[tokio::test]
+async fn test_struct_too_many_rows() {
+    let err = sql!("SELECT id, name FROM users ORDER BY id")
+        .await
+        .map(|_: User| ())
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::TooManyRows), "{err:?}");
+
+    let err = sql!("SELECT id, name FROM users ORDER BY id")
+        .await
+        .map(|_: Option<User>| ())
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::TooManyRows), "{err:?}");
+}
+
+#[tokio::test]
+async fn test_transaction_too_many_rows() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+    let err = sql!("SELECT id FROM users ORDER BY id")
+        .run_with(&tx)
+        .await
+        .map(|_: i64| ())
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::TooManyRows), "{err:?}");
+
+    let err = sql!("SELECT id FROM users WHERE id = -1")
+        .run_with(&tx)
+        .await
+        .map(|_: i64| ())
+        .unwrap_err();
+    assert!(matches!(err.kind(), ErrorKind::RowNotFound), "{err:?}");
+}
+
+#[tokio::test]
+async fn test_row_not_found() {
+    let err = sql!("SELECT id FROM users WHERE id = -1")
+        .await
+        .map(|_: i64| ())
+        .unwrap_err();
+    assert!(err.is_row_not_found(), "{err:?}");
+}
+
+#[tokio::test]
+async fn test_first() {
+    let id: i64 = sql!("SELECT id FROM users ORDER BY id DESC")
+        .first()
+        .await
+        .unwrap();
+    assert_eq!(id, 2);
+
+    let user: Option<User> = sql!("SELECT id, name FROM users ORDER BY id")
+        .first()
+        .await
+        .unwrap();
+    assert_eq!(
+        user,
+        Some(User {
+            id: 1,
+            name: Some("first".to_string())
+        })
+    );
+
+    let user: Option<User> = sql!("SELECT id, name FROM users WHERE id = -1")
+        .first()
+        .await
+        .unwrap();
+    assert_eq!(user, None);
+}
+
+#[tokio::test]
+async fn test_first_run_with() {
+    let conn = connect().await.unwrap();
+    let user: User = sql!("SELECT id, name FROM users ORDER BY id DESC")
+        .first()
+        .run_with(&conn)
+        .await
+        .unwrap();
+    assert_eq!(user, User { id: 2, name: None });
+
+    let err = sql!("SELECT id FROM users WHERE id = -1")
+        .first()
+        .run_with(&conn)
+        .await
+        .map(|_: i64| ())
+        .unwrap_err();
+    assert!(err.is_row_not_found(), "{err:?}");
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use sqlm_postgres::{sql, telemetry};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber, subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::Registry;
+
+type Fields = HashMap<&'static str, String>;
+
+#[derive(Default, Clone)]
+struct Capture(Arc<Mutex<Vec<Fields>>>);
+
+struct Visitor<'a>(&'a mut Fields);
+
+impl Visit for Visitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name(), format!("{value:?}"));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name(), value.to_string());
+    }
+}
+
+impl<S: Subscriber> Layer<S> for Capture {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if *event.metadata().level() != Level::WARN {
+            return;
+        }
+        let mut fields = Fields::new();
+        event.record(&mut Visitor(&mut fields));
+        self.0.lock().unwrap().push(fields);
+    }
+}
+
+#[tokio::test]
+async fn test_slow_query_warning() {
+    let capture = Capture::default();
+    let _guard = subscriber::set_default(Registry::default().with(capture.clone()));
+
+    let id = 1i64;
+    let name: String = sql!("SELECT name FROM users WHERE id = {id}")
+        .await
+        .unwrap();
+    assert_eq!(name, "first");
+    assert!(capture.0.lock().unwrap().is_empty());
+
+    telemetry::set_slow_query_threshold(Some(Duration::ZERO));
+    telemetry::set_explain_slow_queries(true);
+    let line = line!() + 1;
+    let name: String = sql!("SELECT name FROM users WHERE id = {id}")
+        .await
+        .unwrap();
+    assert_eq!(name, "first");
+    telemetry::set_slow_query_threshold(None);
+
+    let events = capture.0.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event["message"], "slow sql query");
+    assert_eq!(
+        event["db.statement"],
+        "SELECT name FROM users WHERE id = $1"
+    );
+    assert!(
+        event["location"].starts_with(&format!("postgres/tests/slow_query_test.rs:{line}:")),
+        "{}",
+        event["location"]
+    );
+    assert!(event["plan"].contains("cost="), "{}", event["plan"]);
+}
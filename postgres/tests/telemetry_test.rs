@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use sqlm_postgres::telemetry::{self, ParameterRecording};
+use sqlm_postgres::{connect, sql};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Subscriber, subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::{LookupSpan, Registry};
+
+type Fields = HashMap<&'static str, String>;
+
+#[derive(Default, Clone)]
+struct Capture(Arc<Mutex<Vec<Fields>>>);
+
+impl Capture {
+    fn spans(&self) -> Vec<Fields> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+struct Index(usize);
+
+struct Visitor<'a>(&'a mut Fields);
+
+impl Visit for Visitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name(), format!("{value:?}"));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name(), value.to_string());
+    }
+}
+
+impl<S> Layer<S> for Capture
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != "sql query" {
+            return;
+        }
+        let mut fields = Fields::new();
+        attrs.record(&mut Visitor(&mut fields));
+        let mut spans = self.0.lock().unwrap();
+        spans.push(fields);
+        ctx.span(id)
+            .unwrap()
+            .extensions_mut()
+            .insert(Index(spans.len() - 1));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        let Some(Index(index)) = span.extensions().get::<Index>().map(|i| Index(i.0)) else {
+            return;
+        };
+        values.record(&mut Visitor(&mut self.0.lock().unwrap()[index]));
+    }
+}
+
+fn capture() -> (Capture, subscriber::DefaultGuard) {
+    let capture = Capture::default();
+    let guard = subscriber::set_default(Registry::default().with(capture.clone()));
+    (capture, guard)
+}
+
+#[tokio::test]
+async fn test_span_fields_select() {
+    let (capture, _guard) = capture();
+
+    let id = 1i64;
+    let name: String = sql!("SELECT name FROM users WHERE id = {id}")
+        .await
+        .unwrap();
+    assert_eq!(name, "first");
+
+    let spans = capture.spans();
+    assert_eq!(spans.len(), 1);
+    let span = &spans[0];
+    assert_eq!(span["db.system"], "postgresql");
+    assert_eq!(span["db.statement"], "SELECT name FROM users WHERE id = $1");
+    assert_eq!(span["db.operation"], "SELECT");
+    assert_eq!(span["db.name"], "sqlm-test");
+    assert_eq!(span["server.address"], "127.0.0.1");
+    assert_eq!(span["db.response.returned_rows"], "1");
+    assert!(!span.contains_key("otel.status_code"));
+}
+
+#[tokio::test]
+async fn test_span_fields_with_connection() {
+    let conn = connect().await.unwrap();
+    let (capture, _guard) = capture();
+
+    let ids: Vec<i64> = sql!("SELECT id FROM users ORDER BY id")
+        .run_with(&conn)
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![1, 2]);
+
+    let spans = capture.spans();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0]["db.name"], "sqlm-test");
+    assert_eq!(spans[0]["db.response.returned_rows"], "2");
+}
+
+#[tokio::test]
+async fn test_span_fields_affected_rows() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+    let (capture, _guard) = capture();
+
+    sql!("UPDATE users SET name = name")
+        .run_with(&tx)
+        .await
+        .unwrap();
+
+    let spans = capture.spans();
+    assert_eq!(spans[0]["db.operation"], "UPDATE");
+    assert_eq!(spans[0]["db.response.affected_rows"], "2");
+}
+
+#[tokio::test]
+async fn test_span_fields_error() {
+    let (capture, _guard) = capture();
+
+    let id = "no number";
+    sql!("SELECT {id}::TEXT::BIGINT AS id")
+        .await
+        .map(|_: i64| ())
+        .unwrap_err();
+
+    let spans = capture.spans();
+    let span = spans.last().unwrap();
+    assert_eq!(span["otel.status_code"], "ERROR");
+    assert_eq!(span["db.response.status_code"], "22P02");
+}
+
+#[tokio::test]
+async fn test_parameter_recording() {
+    let (capture, _guard) = capture();
+
+    let id = 1i64;
+    let name = "first";
+    let _: Option<i64> = sql!("SELECT id FROM users WHERE id = {id} AND name = {name}")
+        .await
+        .unwrap();
+
+    let _: Option<i64> = sql!("SELECT id FROM users WHERE id = {id} AND name = {name:secret}")
+        .await
+        .unwrap();
+
+    telemetry::set_parameter_recording(ParameterRecording::Types);
+    let _: Option<i64> = sql!("SELECT id FROM users WHERE id = {id} AND name = {name}")
+        .await
+        .unwrap();
+
+    telemetry::set_parameter_recording(ParameterRecording::Redacted);
+    let _: Option<i64> = sql!("SELECT id FROM users WHERE id = {id} AND name = {name}")
+        .await
+        .unwrap();
+
+    telemetry::set_parameter_recording(ParameterRecording::Off);
+    let _: Option<i64> = sql!("SELECT id FROM users WHERE id = {id} AND name = {name}")
+        .await
+        .unwrap();
+
+    telemetry::set_parameter_recording(ParameterRecording::Values);
+
+    let spans = capture.spans();
+    assert_eq!(spans[0]["parameters"], r#"[1, "first"]"#);
+    assert_eq!(spans[1]["parameters"], "[1, <redacted>]");
+    assert_eq!(spans[2]["parameters"], r#"["int8", "text"]"#);
+    assert_eq!(spans[3]["parameters"], "[<redacted>, <redacted>]");
+    assert!(!spans[4].contains_key("parameters"));
+}
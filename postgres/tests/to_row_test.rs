@@ -0,0 +1,245 @@
+use sqlm_postgres::{Enum, FromRow, FromSql, ToRow, ToSql, connect, sql};
+
+#[derive(Debug, Default, FromSql, ToSql, Enum, PartialEq, Eq)]
+#[postgres(name = "role")]
+enum Role {
+    #[default]
+    #[postgres(name = "user")]
+    User,
+    #[postgres(name = "admin")]
+    Admin,
+}
+
+#[derive(ToRow)]
+struct NewAccount {
+    user_id: i64,
+    email: String,
+}
+
+#[derive(ToRow)]
+struct AccountPatch {
+    user_id: Option<i64>,
+    email: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq, FromRow, ToRow)]
+struct Account {
+    user_id: i64,
+    email: String,
+}
+
+#[derive(ToRow)]
+struct NewUser {
+    name: Option<String>,
+    #[sqlm(pg_type = "role")]
+    role: Role,
+}
+
+mod models {
+    #[derive(sqlm_postgres::ToRow)]
+    pub struct NewUser {
+        pub name: Option<String>,
+    }
+}
+
+#[tokio::test]
+async fn test_insert() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+
+    let account = NewAccount {
+        user_id: 2,
+        email: "insert@example.com".to_string(),
+    };
+    let inserted: Account =
+        sql!("INSERT INTO accounts {account:insert(NewAccount)} RETURNING {Account::*}")
+            .run_with(&tx)
+            .await
+            .unwrap();
+    assert_eq!(
+        inserted,
+        Account {
+            user_id: 2,
+            email: "insert@example.com".to_string(),
+        }
+    );
+
+    let user = models::NewUser {
+        name: Some("inserted".to_string()),
+    };
+    let name: Option<String> = sql!(
+        "INSERT INTO users {0:insert(models::NewUser)} RETURNING name",
+        &user
+    )
+    .run_with(&tx)
+    .await
+    .unwrap();
+    assert_eq!(name.as_deref(), Some("inserted"));
+
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_insert_from_row_struct() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+
+    let account = Account {
+        user_id: 2,
+        email: "both@example.com".to_string(),
+    };
+    let inserted: Account =
+        sql!("INSERT INTO accounts {account:insert(Account)} RETURNING {Account::*}")
+            .run_with(&tx)
+            .await
+            .unwrap();
+    assert_eq!(inserted, account);
+
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_insert_evaluates_argument_once() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+
+    let calls = std::cell::Cell::new(0);
+    let new_account = || {
+        calls.set(calls.get() + 1);
+        NewAccount {
+            user_id: 2,
+            email: "once@example.com".to_string(),
+        }
+    };
+    let inserted: Account = sql!(
+        "INSERT INTO accounts {account:insert(NewAccount)} RETURNING {Account::*}",
+        account = new_account()
+    )
+    .run_with(&tx)
+    .await
+    .unwrap();
+    assert_eq!(inserted.email, "once@example.com");
+    assert_eq!(calls.get(), 1);
+
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_set() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+
+    let patch = AccountPatch {
+        user_id: None,
+        email: Some("patched@example.com".to_string()),
+    };
+    let account: Account = sql!(
+        "UPDATE accounts SET {patch:set(AccountPatch)} WHERE email = 'first@example.com'
+         RETURNING {Account::*}"
+    )
+    .run_with(&tx)
+    .await
+    .unwrap();
+    assert_eq!(
+        account,
+        Account {
+            user_id: 1,
+            email: "patched@example.com".to_string(),
+        }
+    );
+
+    let patch = AccountPatch {
+        user_id: Some(2),
+        email: None,
+    };
+    let account: Account = sql!(
+        "UPDATE accounts SET {patch:set(AccountPatch)} WHERE email = {email} RETURNING {Account::*}",
+        email = "patched@example.com"
+    )
+    .run_with(&tx)
+    .await
+    .unwrap();
+    assert_eq!(
+        account,
+        Account {
+            user_id: 2,
+            email: "patched@example.com".to_string(),
+        }
+    );
+
+    // fields that aren't an `Option` are always assigned
+    let account = Account {
+        user_id: 1,
+        email: "replaced@example.com".to_string(),
+    };
+    let replaced: Account = sql!(
+        "UPDATE accounts SET {account:set(Account)} WHERE email = 'patched@example.com'
+         RETURNING {Account::*}"
+    )
+    .run_with(&tx)
+    .await
+    .unwrap();
+    assert_eq!(replaced, account);
+
+    tx.rollback().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_unnest() {
+    let mut conn = connect().await.unwrap();
+    let tx = conn.transaction().await.unwrap();
+
+    let accounts = vec![
+        NewAccount {
+            user_id: 2,
+            email: "first@example.com".to_string(),
+        },
+        NewAccount {
+            user_id: 2,
+            email: "unnest@example.com".to_string(),
+        },
+    ];
+    let mut inserted: Vec<Account> = sql!(
+        "INSERT INTO accounts (user_id, email) {accounts:unnest(NewAccount)}
+         ON CONFLICT (email) DO UPDATE SET user_id = EXCLUDED.user_id
+         RETURNING {Account::*}"
+    )
+    .run_with(&tx)
+    .await
+    .unwrap();
+    inserted.sort_by(|a, b| a.email.cmp(&b.email));
+    assert_eq!(
+        inserted,
+        vec![
+            Account {
+                user_id: 2,
+                email: "first@example.com".to_string(),
+            },
+            Account {
+                user_id: 2,
+                email: "unnest@example.com".to_string(),
+            },
+        ]
+    );
+
+    let users = [
+        NewUser {
+            name: Some("admin".to_string()),
+            role: Role::Admin,
+        },
+        NewUser {
+            name: None,
+            role: Role::User,
+        },
+    ];
+    let roles: Vec<Role> = sql!(
+        "INSERT INTO users (name, role) {0:unnest(NewUser)} RETURNING role",
+        &users[..]
+    )
+    .run_with(&tx)
+    .await
+    .unwrap();
+    assert_eq!(roles, vec![Role::Admin, Role::User]);
+
+    tx.rollback().await.unwrap();
+}